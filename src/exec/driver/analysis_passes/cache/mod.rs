@@ -0,0 +1,105 @@
+use rustc::mir::Mir;
+use rustc::ich::StableHashingContext;
+use rustc::session::Session;
+use rustc_data_structures::stable_hasher::{StableHasher,HashStable};
+use rustc_serialize::json;
+use std::fs;
+use std::path::PathBuf;
+use super::sir::Summary;
+
+/*
+ * Part of every cache key. Bump it with any change to what the analyses report or to the summaries
+ * they compute, so that results cached by an older rure are not reused.
+ */
+pub const CACHE_VERSION: u32 = 3;
+
+pub struct CacheKey {
+	def_path: String,
+	mir_hash: u64
+}
+
+impl CacheKey {
+	pub fn new(def_path: String, mir_hash: u64) -> CacheKey {
+		CacheKey {
+			def_path,
+			mir_hash
+		}
+	}
+
+	pub fn mir_hash(&self) -> u64 {
+		self.mir_hash
+	}
+}
+
+#[derive(RustcEncodable,RustcDecodable)]
+pub struct CacheEntry {
+	mir_hash: u64,
+	errors: Vec<(String, Vec<(String,String)>)>,
+	summary: Summary
+}
+
+impl CacheEntry {
+	pub fn new(mir_hash: u64, errors: Vec<(String, Vec<(String,String)>)>, summary: &Summary) -> CacheEntry {
+		CacheEntry {
+			mir_hash,
+			errors,
+			summary: summary.detached()
+		}
+	}
+
+	pub fn into_parts(self) -> (Vec<(String, Vec<(String,String)>)>, Summary) {
+		(self.errors, self.summary)
+	}
+}
+
+pub struct ResultCache {
+	dir: Option<PathBuf>
+}
+
+impl ResultCache {
+	pub fn new(dir: Option<PathBuf>) -> ResultCache {
+		if let Some(dir) = &dir {
+			let _ = fs::create_dir_all(dir);
+		}
+
+		ResultCache {
+			dir
+		}
+	}
+
+	fn entry_path(&self, key: &CacheKey) -> Option<PathBuf> {
+		self.dir.as_ref().map(|dir| dir.join(format!("{}.json", key.def_path)))
+	}
+
+	pub fn load(&self, key: &CacheKey) -> Option<CacheEntry> {
+		let contents = fs::read_to_string(self.entry_path(key)?).ok()?;
+		let entry: CacheEntry = json::decode(&contents).ok()?;
+		if entry.mir_hash == key.mir_hash {
+			Some(entry)
+		} else {
+			None
+		}
+	}
+
+	/*
+	 * Writes entry under key. A cache that cannot be written only costs the next run its time, so
+	 * failures are warnings.
+	 */
+	pub fn store(&self, key: &CacheKey, entry: CacheEntry, sess: &Session) {
+		if let Some(path) = self.entry_path(key) {
+			let written = json::encode(&entry).map_err(|err| err.to_string())
+				.and_then(|contents| fs::write(&path, contents).map_err(|err| err.to_string()));
+			if let Err(err) = written {
+				sess.warn(&format!("could not write the rure cache entry {}: {}", path.display(), err));
+			}
+		}
+	}
+}
+
+/*
+ * Hashes mir the way incremental compilation does, so that the hash names items by their def path
+ * hashes and stays the same across compiler sessions.
+ */
+pub fn hash_mir<'a>(mir: &Mir, hcx: &mut StableHashingContext<'a>, hasher: &mut StableHasher<u64>) {
+	mir.hash_stable(hcx, hasher);
+}
@@ -1,21 +1,28 @@
-use rustc::mir::Local;
 use crate::exec::driver::analysis_passes::smt::solve_sir;
-use rustc::mir::Mir;
+use rustc::mir::{Mir,Local};
 use rustc::ty::TyCtxt;
 use rustc::hir::def_id::DefId;
-use rustc_mir::transform::inline::Inline;
-use rustc_mir::transform::MirSource;
-use rustc::ty::InstanceDef;
-use rustc_mir::transform::MirPass;
+use rustc::ty::{Instance,ParamEnv,TyKind,TypeFoldable,RegionKind,AssocKind};
+use rustc::ty::subst::InternalSubsts;
+use rustc::ty::layout::VariantIdx;
+use rustc::mir::TerminatorKind;
 use std::collections::HashMap;
-use self::sir::{MirVariableProp,Rator,Expr,SymTy};
+use std::hash::Hash;
+use rustc_data_structures::stable_hasher::StableHasher;
+use self::sir::{MirVariableProp,Rator,Expr,SymTy,VarLocation,PathElem};
 use syntax_pos::Span;
+use syntax::ast;
+use syntax::ptr::P;
 
 mod symb_exec;
 mod sir;
 mod smt;
+mod cache;
 
-use symb_exec::ExecutionContext;
+use symb_exec::{ExecutionContext,Typing,MAX_CALL_DEPTH,callee_instance,contract_source,clause_source};
+pub use symb_exec::{SummaryCache,Assumptions,has_contract};
+pub use cache::ResultCache;
+use cache::{CacheKey,CacheEntry,CACHE_VERSION,hash_mir};
 
 #[derive(PartialEq,Debug)]
 pub struct ErrorInfo {
@@ -26,18 +33,16 @@ pub struct ErrorInfo {
 }
 
 impl ErrorInfo {
-	fn from(entry_id: DefId, model: HashMap<(DefId,Local), SymTy>, mir: &Mir, compiler: &TyCtxt) -> ErrorInfo {
-		let error_type = "Null Dereference".to_owned();
-
-
-		 let assignments = (0..mir.arg_count).filter_map(|i| {
-			let lcl = i + 1;
-			let mp = (entry_id,Local::from(lcl));
-			if let Some(val) = model.get(&mp) {
-				Some((format!("Argument {}: ",lcl), format!("{:?}",val)))
-			} else {
-				None
-			}}).collect();
+	fn from<'a,'tcx>(entry_id: DefId, error_type: &str, model: HashMap<VarLocation, SymTy>, mir: &'a Mir<'tcx>, compiler: TyCtxt<'a,'tcx,'tcx>) -> ErrorInfo {
+		let error_type = error_type.to_owned();
+
+		let mut args: Vec<_> = model.iter().filter(|((did, lcl, _), _)| {
+			*did == entry_id && lcl.index() >= 1 && lcl.index() <= mir.arg_count
+		}).collect();
+		args.sort_by(|((_, lcl1, path1), _), ((_, lcl2, path2), _)| (lcl1.index(), path1).cmp(&(lcl2.index(), path2)));
+
+		let typing = Typing::new(mir, compiler);
+		let assignments = args.into_iter().filter_map(|((_, lcl, path), val)| describe(entry_id, *lcl, path, val, &model, typing)).collect();
 		
 		ErrorInfo {
 			error_type,
@@ -47,6 +52,19 @@ impl ErrorInfo {
 		}
 	}
 
+	fn from_cached(entry_id: DefId, error_type: String, assignments: Vec<(String,String)>, mir: &Mir) -> ErrorInfo {
+		ErrorInfo {
+			error_type,
+			assignments,
+			span: mir.span,
+			def_id: entry_id
+		}
+	}
+
+	fn to_cached(&self) -> (String, Vec<(String,String)>) {
+		(self.error_type.clone(), self.assignments.clone())
+	}
+
 	pub fn get_span(&self) -> Span {
 		self.span
 	}
@@ -68,6 +86,48 @@ impl ErrorInfo {
 	}
 }
 
+/*
+ * Names the argument leaf at path, spelling out the enum variants it passes through. Leaves
+ * inside a variant that is not active in the model are skipped.
+ */
+fn describe<'a,'tcx>(entry_id: DefId, lcl: Local, path: &[PathElem], val: &SymTy, model: &HashMap<VarLocation, SymTy>, typing: Typing<'a,'tcx>) -> Option<(String,String)> {
+	let mut ty = typing.local_ty(lcl);
+	let mut variant_tys = None;
+	let mut label = format!("Argument {}", lcl.index());
+	for (i, elem) in path.iter().enumerate() {
+		match (elem, &ty.sty) {
+			(PathElem::Field(field), _) => {
+				let tys = match variant_tys.take() {
+					Some(tys) => tys,
+					None => typing.field_tys(ty)?
+				};
+				ty = tys[*field];
+				label.push_str(&elem.to_string());
+			},
+			(PathElem::Variant(variant), &TyKind::Adt(def, substs)) => {
+				let idx = VariantIdx::from(*variant);
+				let mut discr_path = path[..i].to_vec();
+				discr_path.push(PathElem::Discriminant);
+				if *model.get(&(entry_id, lcl, discr_path))? != typing.discriminant(def, idx) {
+					return None;
+				}
+				label.push_str(&format!("::{}", def.variants[idx].ident));
+				variant_tys = Some(typing.variant_tys(&def.variants[idx], substs));
+			},
+			(PathElem::Length, _) => label.push_str(&elem.to_string()),
+			(PathElem::Discriminant, &TyKind::Adt(def, _)) => {
+				let name = def.variants.iter_enumerated().find(|(idx, _)| typing.discriminant(def, *idx) == *val)
+					.map(|(_, variant)| variant.ident.to_string())
+					.unwrap_or(format!("{}", val));
+				return Some((format!("{} variant: ", label), name));
+			},
+			_ => return None
+		}
+	}
+
+	Some((format!("{}: ", label), format!("{}", val)))
+}
+
 #[derive(PartialEq)]
 pub enum PassResult {
 	AssertiveOk,
@@ -76,52 +136,158 @@ pub enum PassResult {
 }
 
 
-pub struct AnalysisHandler<'a,'tcx,'gcx> {
+pub struct AnalysisHandler<'a,'tcx: 'a> {
 	start: DefId,
+	instance: Instance<'tcx>,
+	impl_of: Option<DefId>,
 	code: Mir<'tcx>,
-	ctx: &'a TyCtxt<'a,'gcx,'tcx>,
+	callees: HashMap<Instance<'tcx>, Mir<'tcx>>,
+	ctx: &'a TyCtxt<'a,'tcx,'tcx>,
 }
 
-impl  <'a,'tcx,'gcx >AnalysisHandler<'a,'tcx, 'gcx> {
-	pub fn new(start: DefId, ctx: &'a rustc::ty::TyCtxt<'_, 'tcx, 'gcx>) -> AnalysisHandler<'a,'tcx,'gcx> {
-		let mut code = ctx.optimized_mir(start).clone();
-		let liner = Inline {
+impl  <'a,'tcx>AnalysisHandler<'a,'tcx> {
+	pub fn new(start: DefId, ctx: &'a TyCtxt<'a, 'tcx, 'tcx>) -> AnalysisHandler<'a,'tcx> {
+		let code = ctx.optimized_mir(start).clone();
+		let instance = Instance::new(start, InternalSubsts::identity_for_item(*ctx, start));
+		AnalysisHandler::from_code(instance, None, code, ctx)
+	}
 
-		};
+	/*
+	 * A handler for the default body of the trait method start, with Self and the parameters of
+	 * the trait taken from impl_did, which inherits it.
+	 */
+	pub fn inherited(start: DefId, impl_did: DefId, ctx: &'a TyCtxt<'a, 'tcx, 'tcx>) -> AnalysisHandler<'a,'tcx> {
+		let trait_substs = ctx.impl_trait_ref(impl_did).unwrap().substs;
+		let substs = InternalSubsts::for_item(*ctx, start, |param, _| {
+			trait_substs.get(param.index as usize).cloned().unwrap_or_else(|| ctx.mk_region(RegionKind::ReErased).into())
+		});
+		let code = ctx.subst_and_normalize_erasing_regions(substs, ParamEnv::reveal_all(), ctx.optimized_mir(start));
+		AnalysisHandler::from_code(Instance::new(start, substs), Some(impl_did), code, ctx)
+	}
 
-		let source = MirSource {
-			instance: InstanceDef::Item(start),
-			promoted: None,
-		};
-		liner.run_pass(*ctx,source, &mut code);
+	fn from_code(instance: Instance<'tcx>, impl_of: Option<DefId>, code: Mir<'tcx>, ctx: &'a TyCtxt<'a, 'tcx, 'tcx>) -> AnalysisHandler<'a,'tcx> {
+		let mut callees = HashMap::new();
+		collect_callees(&code, ctx, 0, &mut callees);
 		AnalysisHandler {
-			start,
+			start: instance.def_id(),
+			instance,
+			impl_of,
 			code,
+			callees,
 			ctx,
 		}
 	}
 
 	
-	pub fn run_all_analyses(&self) -> Vec<ErrorInfo> {
+	fn cache_key(&self, assumed: &[P<ast::Expr>]) -> CacheKey {
+		let mut hcx = self.ctx.create_stable_hashing_context();
+		let mut hasher = StableHasher::<u64>::new();
+		(env!("CARGO_PKG_VERSION"), CACHE_VERSION).hash(&mut hasher);
+		hash_mir(&self.code, &mut hcx, &mut hasher);
+		contract_source(self.start, *self.ctx).hash(&mut hasher);
+		clause_source(assumed).hash(&mut hasher);
+
+		let mut callees: Vec<_> = self.callees.iter().map(|(instance, mir)| (instance.to_string(), instance.def_id(), mir)).collect();
+		callees.sort_by(|(name1, _, _), (name2, _, _)| name1.cmp(name2));
+		for (name, did, mir) in callees {
+			name.hash(&mut hasher);
+			hash_mir(mir, &mut hcx, &mut hasher);
+			contract_source(did, *self.ctx).hash(&mut hasher);
+		}
+
+		let def_path = match self.impl_of {
+			Some(impl_did) => format!("{}-{}", self.ctx.def_path_hash(self.start).0.to_hex(), self.ctx.def_path_hash(impl_did).0.to_hex()),
+			None => self.ctx.def_path_hash(self.start).0.to_hex()
+		};
+		CacheKey::new(def_path, hasher.finish())
+	}
+
+	pub fn run_all_analyses(&self, summaries: &mut SummaryCache<'tcx>, cache: &ResultCache, assumptions: &Assumptions) -> Vec<ErrorInfo> {
+			let assumed = assumptions.of(self.start, *self.ctx);
+			let key = self.cache_key(&assumed);
+			if let Some(entry) = cache.load(&key) {
+				let (errors, summary) = entry.into_parts();
+				summaries.insert(self.instance, summary);
+				return errors.into_iter().map(|(error_type, assignments)| ErrorInfo::from_cached(self.start, error_type, assignments, &self.code)).collect();
+			}
+
 			let mut mirs = HashMap::new();
-			mirs.insert(self.start, &self.code);
-			let (sir, entryid) = ExecutionContext::create_from_entry(self.start, mirs).evaluate();
+			mirs.insert(self.instance, &self.code);
+			for (instance, mir) in self.callees.iter() {
+				mirs.entry(*instance).or_insert(mir);
+			}
+			let (sir, entryid, summary) = ExecutionContext::create_from_entry(self.instance, assumed, mirs, summaries, *self.ctx).evaluate();
 			
-			let vals = sir.get_all_names().filter_map(|x| if let Some(prop) = sir.get_declaration(x).get_property().first() {
-				Some((x, prop))
-			} else {
-				None
-			});
+			let vals = sir.get_all_names().flat_map(|x| sir.get_declaration(x).get_property().iter().map(move |prop| (x, prop)));
 			
 			let mut errs = Vec::new();
-			for (interested_name, MirVariableProp::IsDerefed(nid) ) in vals {
-				let assign = Expr::BinOp(Rator::Eq, Box::new(Expr::Ref(interested_name)), Box::new(Expr::Value(SymTy::Integer(0))));
-				let pc = sir.get_path_constraint(*nid);
-				let add = vec![pc,assign];
+			for (interested_name, prop) in vals {
+				let (error_type, violation) = match prop {
+					MirVariableProp::IsDerefed(_) => ("Null Dereference", Expr::BinOp(Rator::Eq, Box::new(Expr::Ref(interested_name)), Box::new(Expr::Value(SymTy::pointer(0))))),
+					MirVariableProp::Obligation{kind, violation, ..} => (kind.message(), violation.clone())
+				};
+				let pc = sir.get_path_constraint(prop.get_node());
+				let add = vec![pc,violation];
 				if let Some(model) = solve_sir(&sir,entryid,add) {
-					errs.push(ErrorInfo::from(self.start, model, &self.code, &self.ctx));
+					let mut err = ErrorInfo::from(self.start, error_type, model, &self.code, *self.ctx);
+					if let Some(impl_did) = self.impl_of {
+						err.assignments.insert(0, ("Self: ".to_owned(), self.ctx.type_of(impl_did).to_string()));
+					}
+					if !errs.contains(&err) {
+						errs.push(err);
+					}
 				}
 			}
+
+			cache.store(&key, CacheEntry::new(key.mir_hash(), errs.iter().map(|err| err.to_cached()).collect(), &summary), self.ctx.sess);
+			summaries.insert(self.instance, summary);
 			errs
 	}
-}
\ No newline at end of file
+}
+
+/*
+ * The local impls that inherit the default body of the trait method did rather than providing
+ * their own, leaving out impls that are themselves generic. Empty unless did is a trait method
+ * without type parameters of its own.
+ */
+pub fn inheriting_impls<'a,'tcx>(did: DefId, tcx: TyCtxt<'a,'tcx,'tcx>) -> Vec<DefId> {
+	let trait_did = match tcx.trait_of_item(did) {
+		Some(trait_did) if tcx.generics_of(did).own_counts().types == 0 => trait_did,
+		_ => return Vec::new()
+	};
+
+	let name = tcx.item_name(did);
+	let mut impls = Vec::new();
+	tcx.for_each_impl(trait_did, |impl_did| {
+		let concrete = tcx.impl_trait_ref(impl_did).map_or(false, |trait_ref| !trait_ref.substs.needs_subst());
+		let overrides = tcx.associated_items(impl_did).any(|item| item.kind == AssocKind::Method && item.ident.as_str() == name.as_str());
+		if impl_did.is_local() && concrete && !overrides {
+			impls.push(impl_did);
+		}
+	});
+	impls
+}
+
+/*
+ * The bodies of the callees of mir, keyed by the instance each call resolves to, so that a generic
+ * called at two types or a trait method called through two impls gets a body for each.
+ */
+fn collect_callees<'a,'tcx>(mir: &Mir<'tcx>, ctx: &TyCtxt<'a,'tcx,'tcx>, depth: usize, callees: &mut HashMap<Instance<'tcx>, Mir<'tcx>>) {
+	if depth >= MAX_CALL_DEPTH {
+		return;
+	}
+
+	for blk in mir.basic_blocks() {
+		if let TerminatorKind::Call{func,..} = &blk.terminator().kind {
+			if let Some(instance) = callee_instance(func, *ctx) {
+				if callees.contains_key(&instance) || !ctx.is_mir_available(instance.def_id()) {
+					continue;
+				}
+
+				let body = ctx.subst_and_normalize_erasing_regions(instance.substs, ParamEnv::reveal_all(), ctx.optimized_mir(instance.def_id()));
+				callees.insert(instance, body.clone());
+				collect_callees(&body, ctx, depth + 1, callees);
+			}
+		}
+	}
+}
@@ -5,10 +5,13 @@ use rustc::mir::Local;
 use std::io::Write;
 use rsmt2::errors::SmtRes;
 use rustc::ty::{Ty, TyKind};
+use rustc::ty::{IntTy, UintTy};
+use std::fmt;
 
 use rustc::mir;
 
 mod structures;
+mod summary;
 use std::io::Cursor;
 
 use rsmt2::print::Expr2Smt;
@@ -16,6 +19,7 @@ use structures::NodeVec;
 use structures::NameVec;
 pub use structures::Name;
 pub use structures::NodeId;
+pub use summary::Summary;
 
 use rsmt2::print::Sort2Smt;
 
@@ -40,6 +44,10 @@ impl Node {
 		self.statements.is_empty()
 	}
 
+	fn get_statements(&self) -> &Vec<Expr> {
+		&self.statements
+	}
+
 	fn to_smt(&self) -> String {
 		let mut res = String::new();
 		for state in self.statements.iter() {
@@ -92,13 +100,21 @@ pub struct Sir {
 	declarations: NameVec<Declaration>,
 	nodes: NodeVec<Node>,
 	forward_edges: NodeVec<Vec<Edge>>,
-	backward_edges: NodeVec<Vec<Edge>>
-
+	backward_edges: NodeVec<Vec<Edge>>,
+	axioms: Vec<Expr>,
+	assumptions: Vec<Expr>
 }
 
 impl Sir {
 	pub fn to_smt(&self, start: NodeId) -> String {
-		self.node_to_smt(start)
+		let mut total = String::new();
+		total.push_str("(and true ");
+		for axiom in self.axioms.iter().chain(self.assumptions.iter()) {
+			total.push_str(&axiom.to_smt());
+		}
+		total.push_str(&self.node_to_smt(start));
+		total.push_str(")");
+		total
 	}
 
 
@@ -130,6 +146,8 @@ impl Sir {
 			nodes: NodeVec::new(),
 			forward_edges: NodeVec::new(),
 			backward_edges: NodeVec::new(),
+			axioms: Vec::new(),
+			assumptions: Vec::new()
 		}
 	}
 
@@ -180,6 +198,25 @@ impl Sir {
 		self.nodes[nid].add_expr(expr);
 	}
 
+	/*
+	 * Axioms hold on every path, such as the range of values a fresh enum discriminant can take.
+	 */
+	pub fn add_axiom(&mut self, expr: Expr) {
+		self.axioms.push(expr);
+	}
+
+	pub fn get_axioms(&self) -> &Vec<Expr> {
+		&self.axioms
+	}
+
+	/*
+	 * Assumptions only hold for the function this sir was built from and are left out of its summary,
+	 * such as the provenance of its arguments being unknown.
+	 */
+	pub fn add_assumption(&mut self, expr: Expr) {
+		self.assumptions.push(expr);
+	}
+
 	pub fn add_edge(&mut self, nid: NodeId, edge: Edge) {
 		self.backward_edges[edge.get_target()].push(Edge::new(edge.get_precondition(),nid));
 		self.forward_edges[nid].push(edge);
@@ -200,10 +237,56 @@ impl Sir {
 		.fold(Expr::Value(SymTy::Bool(true)), |x, y| Expr::BinOp(Rator::And, Box::new(x), Box::new(y)))
 	}
 
+	pub fn get_path_formula(&self, nid: NodeId) -> Expr {
+		let mut total_exp = self.nodes[nid].get_statements().clone();
+		let mut cid = nid;
+		let mut pred = self.get_in_edges(cid).clone().pop();
+		while let Some(before) = pred {
+			if let Some(pre) = before.get_precondition() {
+				total_exp.push(pre);
+			}
+			cid = before.get_target();
+			total_exp.extend(self.nodes[cid].get_statements().iter().cloned());
+			pred = self.get_in_edges(cid).clone().pop();
+		}
+
+		total_exp.into_iter()
+		.fold(Expr::Value(SymTy::Bool(true)), |x, y| Expr::BinOp(Rator::And, Box::new(x), Box::new(y)))
+	}
+
 }
 
-#[derive(Debug)]
-pub struct Declaration(SymTy, Vec<MirVariableProp>, Option<(DefId,Local)>);
+#[derive(Debug,Clone,PartialEq,Eq,PartialOrd,Ord,Hash,RustcEncodable,RustcDecodable)]
+pub enum PathElem {
+	Field(usize),
+	Discriminant,
+	Variant(usize),
+	Length
+}
+
+impl fmt::Display for PathElem {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			PathElem::Field(i) => write!(f, ".{}", i),
+			PathElem::Discriminant => write!(f, ".discriminant"),
+			PathElem::Variant(i) => write!(f, "::{}", i),
+			PathElem::Length => write!(f, ".len")
+		}
+	}
+}
+
+pub type VarLocation = (DefId, Local, Vec<PathElem>);
+
+pub fn extend_location(loc: &Option<VarLocation>, elem: PathElem) -> Option<VarLocation> {
+	loc.as_ref().map(|(did, lcl, path)| {
+		let mut path = path.clone();
+		path.push(elem);
+		(*did, *lcl, path)
+	})
+}
+
+#[derive(Debug,Clone,RustcEncodable,RustcDecodable)]
+pub struct Declaration(SymTy, Vec<MirVariableProp>, Option<VarLocation>);
 
 impl Declaration {
 	fn add_property(&mut self, prop: MirVariableProp) {
@@ -214,34 +297,121 @@ impl Declaration {
 		Declaration(self.0.clone(), vec![], self.2.clone())
 	}
 
+	pub fn without_location(&self) -> Declaration {
+		Declaration(self.0.clone(), vec![], None)
+	}
+
 	pub fn get_property(&self) -> &Vec<MirVariableProp> {
 		&self.1
 	}
 
-	pub fn get_location(&self) -> &Option<(DefId,Local)> {
+	pub fn get_location(&self) -> &Option<VarLocation> {
 		&self.2
 	}
 }
 
 
-#[derive(Debug)]
+/*
+ * A property of a declaration. An obligation carries the condition under which the operation at
+ * its node is undefined behaviour, or panics for the arithmetic checks the compiler inserts and for
+ * debug_assert!, or breaks a contract clause.
+ */
+#[derive(Debug,Clone,RustcEncodable,RustcDecodable)]
 pub enum MirVariableProp {
-	IsDerefed(NodeId)
+	IsDerefed(NodeId),
+	Obligation{kind: ObligationKind, node: NodeId, violation: Expr}
 }
 
-#[derive(Debug,Clone)]
+impl MirVariableProp {
+	pub fn get_node(&self) -> NodeId {
+		match self {
+			MirVariableProp::IsDerefed(nid) => *nid,
+			MirVariableProp::Obligation{node, ..} => *node
+		}
+	}
+
+	pub fn at_node(&self, nid: NodeId) -> MirVariableProp {
+		match self {
+			MirVariableProp::IsDerefed(_) => MirVariableProp::IsDerefed(nid),
+			MirVariableProp::Obligation{kind, violation, ..} => MirVariableProp::Obligation{kind: *kind, node: nid, violation: violation.clone()}
+		}
+	}
+
+	pub fn rename(&self, f: &impl Fn(&Name) -> Name) -> MirVariableProp {
+		match self {
+			MirVariableProp::IsDerefed(nid) => MirVariableProp::IsDerefed(*nid),
+			MirVariableProp::Obligation{kind, node, violation} => MirVariableProp::Obligation{kind: *kind, node: *node, violation: violation.rename(f)}
+		}
+	}
+}
+
+/*
+ * The kinds of undefined behaviour and panics an obligation may stand for.
+ */
+#[derive(Debug,Clone,Copy,PartialEq,RustcEncodable,RustcDecodable)]
+pub enum ObligationKind {
+	OutOfBounds,
+	UseAfterFree,
+	DoubleFree,
+	DanglingReturn,
+	Misaligned,
+	InvalidValue,
+	DivisionByZero,
+	DivisionOverflow,
+	ShiftOverflow,
+	ArithmeticOverflow,
+	InexactDivision,
+	OffsetOutOfBounds,
+	UninitRead,
+	AliasingViolation,
+	ReadOnlyWrite,
+	ReachedUnreachable,
+	PreconditionViolation,
+	PostconditionViolation
+}
+
+impl ObligationKind {
+	/*
+	 * The name the kind is reported under.
+	 */
+	pub fn message(&self) -> &'static str {
+		match self {
+			ObligationKind::OutOfBounds => "Out of Bounds Access",
+			ObligationKind::UseAfterFree => "Use After Free",
+			ObligationKind::DoubleFree => "Double Free",
+			ObligationKind::DanglingReturn => "Dangling Reference",
+			ObligationKind::Misaligned => "Misaligned Dereference",
+			ObligationKind::InvalidValue => "Invalid Value",
+			ObligationKind::DivisionByZero => "Division by Zero",
+			ObligationKind::DivisionOverflow => "Division Overflow",
+			ObligationKind::ShiftOverflow => "Shift Overflow",
+			ObligationKind::ArithmeticOverflow => "Arithmetic Overflow",
+			ObligationKind::InexactDivision => "Inexact Division",
+			ObligationKind::OffsetOutOfBounds => "Out of Bounds Offset",
+			ObligationKind::UninitRead => "Uninitialized Read",
+			ObligationKind::AliasingViolation => "Aliasing Violation",
+			ObligationKind::ReadOnlyWrite => "Write to Read-Only Memory",
+			ObligationKind::ReachedUnreachable => "Reachable Unreachable",
+			ObligationKind::PreconditionViolation => "Precondition Violation",
+			ObligationKind::PostconditionViolation => "Postcondition Violation"
+		}
+	}
+}
+
+#[derive(Debug,Clone,RustcEncodable,RustcDecodable)]
 pub enum Expr {
 	Value(SymTy),
 	Ref(Name),
 	BinOp(Rator, Box<Expr>, Box<Expr>),
-	UnOp(Rator, Box<Expr>)
+	UnOp(Rator, Box<Expr>),
+	Ite(Box<Expr>, Box<Expr>, Box<Expr>)
 }
 
 /*
  * Rators should be seperated out and type checked against Logics. 
  *
  */
- #[derive(Debug,Clone)]
+ #[derive(Debug,Clone,RustcEncodable,RustcDecodable)]
 pub enum Rator {
 	Eq,
 	Add,
@@ -256,77 +426,213 @@ pub enum Rator {
 	LessThan,
 	Not,
 	Neg,
-	And
-	/*
-		BitVector stuff should go here later
-	*/
-
+	And,
+	Or,
+	SignedDiv,
+	SignedMod,
+	SignedGreaterEqual,
+	SignedGreaterThan,
+	SignedLessEqual,
+	SignedLessThan,
+	BitNot,
+	Xor,
+	BitAnd,
+	BitOr,
+	BitXor,
+	ShiftLeft,
+	LogicalShiftRight,
+	ArithmeticShiftRight,
+	ZeroExtend(u64),
+	SignExtend(u64),
+	Extract(u64, u64)
 }
 
 impl Rator {
-	pub fn from_mir_bin(op: &mir::BinOp) -> Rator {
+	pub fn from_mir_bin(op: &mir::BinOp, ty: Ty) -> Rator {
+		let signed = BitWidth::of(ty).map(|w| w.is_signed()).unwrap_or(false);
 		match op {
-			mir::BinOp::Add => Rator::Add,
+			mir::BinOp::Add | mir::BinOp::Offset => Rator::Add,
 			mir::BinOp::Sub => Rator::Sub,
 			mir::BinOp::Mul => Rator::Mul,
+			mir::BinOp::Div if signed => Rator::SignedDiv,
 			mir::BinOp::Div => Rator::Div,
+			mir::BinOp::Rem if signed => Rator::SignedMod,
 			mir::BinOp::Rem => Rator::Mod,
 			mir::BinOp::Eq => Rator::Eq,
+			mir::BinOp::Lt if signed => Rator::SignedLessThan,
 			mir::BinOp::Lt => Rator::LessThan,
+			mir::BinOp::Le if signed => Rator::SignedLessEqual,
 			mir::BinOp::Le => Rator::LessEqual,
+			mir::BinOp::Gt if signed => Rator::SignedGreaterThan,
 			mir::BinOp::Gt => Rator::GreaterThan,
+			mir::BinOp::Ge if signed => Rator::SignedGreaterEqual,
 			mir::BinOp::Ge => Rator::GreaterEqual,
 			mir::BinOp::Ne => Rator::NotEqual,
-			_ => unimplemented!()
+			mir::BinOp::BitAnd if ty.is_bool() => Rator::And,
+			mir::BinOp::BitAnd => Rator::BitAnd,
+			mir::BinOp::BitOr if ty.is_bool() => Rator::Or,
+			mir::BinOp::BitOr => Rator::BitOr,
+			mir::BinOp::BitXor if ty.is_bool() => Rator::Xor,
+			mir::BinOp::BitXor => Rator::BitXor,
+			mir::BinOp::Shl => Rator::ShiftLeft,
+			mir::BinOp::Shr if signed => Rator::ArithmeticShiftRight,
+			mir::BinOp::Shr => Rator::LogicalShiftRight,
 		}
 	}
 
-	pub fn from_mir_un(op: &mir::UnOp) -> Rator {
+	pub fn from_mir_un(op: &mir::UnOp, ty: Ty) -> Rator {
 		match op {
-			mir::UnOp::Not => Rator::Not,
+			mir::UnOp::Not if ty.is_bool() => Rator::Not,
+			mir::UnOp::Not => Rator::BitNot,
 			mir::UnOp::Neg => Rator::Neg,
 		}
 	}
 }
 
 impl Declaration {
-	pub fn decl_from(ty: Ty, arg_loc: Option<(DefId,Local)>) -> Declaration {	
+	pub fn of_sort(sort: SymTy) -> Declaration {
+		Declaration(sort, vec![], None)
+	}
+
+	pub fn of_sort_at(sort: SymTy, loc: Option<VarLocation>) -> Declaration {
+		Declaration(sort, vec![], loc)
+	}
+
+	/*
+	 * A declaration of the sort of ty. A type without a sort of its own, such as a float, is given
+	 * the sort of a pointer.
+	 */
+	pub fn decl_from(ty: Ty, arg_loc: Option<VarLocation>) -> Declaration {	
 		Declaration(match ty.sty {
 			TyKind::Bool => SymTy::Bool(false),
-			TyKind::Int(_) => SymTy::Integer(0),
-			TyKind::Uint(_) => SymTy::Integer(0),
-			TyKind::RawPtr(_) => SymTy::Integer(0),
-			_ => unimplemented!()}, vec![],arg_loc)
+			_ => match BitWidth::of(ty) {
+				Some(width) => SymTy::Integer(0, width),
+				None => SymTy::pointer(0)
+			}}, vec![],arg_loc)
+	}
+
+	pub fn get_type(&self) -> &SymTy {
+		&self.0
 	}
 }
 
 impl Sort2Smt for Declaration {
 	fn sort_to_smt2<T: Write>(&self, w: &mut T) -> SmtRes<()> {
-		write!(w,"{}",match self.0 {
-			SymTy::Integer(_) => "Int",
-			SymTy::Bool(_) => "Bool"
-		})?;
+		match self.0 {
+			SymTy::Integer(_, width) => write!(w,"(_ BitVec {})", width.bits)?,
+			SymTy::Bool(_) => write!(w,"Bool")?
+		};
 		Ok(())
 	} 
 }
 
-#[derive(Debug,Clone,Eq,PartialEq,Hash)]
+pub const POINTER_WIDTH: u64 = 64;
+
+#[derive(Debug,Clone,Copy,Eq,PartialEq,Hash,RustcEncodable,RustcDecodable)]
+pub struct BitWidth {
+	bits: u64,
+	signed: bool
+}
+
+impl BitWidth {
+	pub fn new(bits: u64, signed: bool) -> BitWidth {
+		BitWidth {
+			bits,
+			signed
+		}
+	}
+
+	pub fn pointer() -> BitWidth {
+		BitWidth::new(POINTER_WIDTH, false)
+	}
+
+	pub fn of(ty: Ty) -> Option<BitWidth> {
+		match ty.sty {
+			TyKind::Int(ity) => Some(BitWidth::new(match ity {
+				IntTy::I8 => 8,
+				IntTy::I16 => 16,
+				IntTy::I32 => 32,
+				IntTy::I64 => 64,
+				IntTy::I128 => 128,
+				IntTy::Isize => POINTER_WIDTH
+			}, true)),
+			TyKind::Uint(uty) => Some(BitWidth::new(match uty {
+				UintTy::U8 => 8,
+				UintTy::U16 => 16,
+				UintTy::U32 => 32,
+				UintTy::U64 => 64,
+				UintTy::U128 => 128,
+				UintTy::Usize => POINTER_WIDTH
+			}, false)),
+			TyKind::Char => Some(BitWidth::new(32, false)),
+			TyKind::RawPtr(_) | TyKind::Ref(..) | TyKind::FnPtr(_) => Some(BitWidth::pointer()),
+			TyKind::Adt(def, _) if def.is_box() => Some(BitWidth::pointer()),
+			_ => None
+		}
+	}
+
+	pub fn get_bits(&self) -> u64 {
+		self.bits
+	}
+
+	pub fn is_signed(&self) -> bool {
+		self.signed
+	}
+
+	pub fn unsigned(&self) -> BitWidth {
+		BitWidth::new(self.bits, false)
+	}
+
+	pub fn mask(&self, val: u128) -> u128 {
+		if self.bits >= 128 {
+			val
+		} else {
+			val & ((1u128 << self.bits) - 1)
+		}
+	}
+
+	/*
+	 * Converts expr from this width to the target width, sign extending if this width is signed.
+	 */
+	pub fn resize(&self, expr: Expr, target: BitWidth) -> Expr {
+		if target.bits > self.bits {
+			let op = if self.signed {
+				Rator::SignExtend(target.bits - self.bits)
+			} else {
+				Rator::ZeroExtend(target.bits - self.bits)
+			};
+			Expr::UnOp(op, Box::new(expr))
+		} else if target.bits < self.bits {
+			Expr::UnOp(Rator::Extract(target.bits - 1, 0), Box::new(expr))
+		} else {
+			expr
+		}
+	}
+}
+
+#[derive(Debug,Clone,Eq,PartialEq,Hash,RustcEncodable,RustcDecodable)]
 pub enum SymTy {
-	Integer(u128),
+	Integer(u128, BitWidth),
 	Bool(bool),	
 }
 
 
 impl SymTy {
+	/*
+	 * The constant sc of type ty. A type without a sort of its own gives a constant of the sort of a
+	 * pointer, as decl_from declares it.
+	 */
 	pub fn from_scalar(sc: u128, ty: Ty) -> SymTy {
 		match ty.sty {
-			TyKind::Int(_) | TyKind::RawPtr(_) | TyKind::Uint(_) => SymTy::Integer(sc),
 			TyKind::Bool => SymTy::Bool(if sc == 1 {
 				true
 			} else {
 				false
 			}),
-			_ => unimplemented!()
+			_ => match BitWidth::of(ty) {
+				Some(width) => SymTy::Integer(width.mask(sc), width),
+				None => SymTy::pointer(BitWidth::pointer().mask(sc))
+			}
 
 		}
 	}
@@ -334,10 +640,44 @@ impl SymTy {
 	pub fn from_boolean(b: bool) -> SymTy {
 		SymTy::Bool(b)
 	}	
+
+	pub fn pointer(addr: u128) -> SymTy {
+		SymTy::Integer(addr, BitWidth::pointer())
+	}
+
+	pub fn with_sort(self, sort: &SymTy) -> SymTy {
+		match (self, sort) {
+			(SymTy::Integer(val, _), SymTy::Integer(_, width)) => SymTy::Integer(width.mask(val), *width),
+			(val, _) => val
+		}
+	}
+}
+
+impl fmt::Display for SymTy {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			SymTy::Bool(bl) => write!(f, "{}", bl),
+			SymTy::Integer(val, width) if width.signed && width.bits < 128 && (val >> (width.bits - 1)) & 1 == 1 => {
+				write!(f, "-{}", (1u128 << width.bits) - val)
+			},
+			SymTy::Integer(val, width) if width.signed => write!(f, "{}", *val as i128),
+			SymTy::Integer(val, _) => write!(f, "{}", val)
+		}
+	}
 }
 
 
 impl Expr {
+	pub fn rename(&self, f: &impl Fn(&Name) -> Name) -> Expr {
+		match self {
+			Expr::Value(val) => Expr::Value(val.clone()),
+			Expr::Ref(nm) => Expr::Ref(f(nm)),
+			Expr::BinOp(op, rand1, rand2) => Expr::BinOp(op.clone(), Box::new(rand1.rename(f)), Box::new(rand2.rename(f))),
+			Expr::UnOp(op, rand) => Expr::UnOp(op.clone(), Box::new(rand.rename(f))),
+			Expr::Ite(cond, thn, els) => Expr::Ite(Box::new(cond.rename(f)), Box::new(thn.rename(f)), Box::new(els.rename(f)))
+		}
+	}
+
 	fn to_smt(&self) -> String {
 		let mut x: Vec<u8> = Vec::new();
 		self.expr_to_smt2(&mut Cursor::new(&mut x),()).unwrap();
@@ -368,6 +708,13 @@ impl rsmt2::print::Expr2Smt<()> for Expr {
 			},
 			Expr::Value(val) => {
 				val.expr_to_smt2(w,());
+			},
+			Expr::Ite(cond,thn,els) => {
+				write!(w,"(ite ");
+				cond.expr_to_smt2(w,());
+				thn.expr_to_smt2(w,());
+				els.expr_to_smt2(w,());
+				write!(w,")");
 			}
 		}
 
@@ -379,19 +726,37 @@ impl rsmt2::print::Expr2Smt<()> for Rator {
 	fn expr_to_smt2<T: std::io::Write>(&self, w: &mut T,_:()) -> SmtRes<()> {
 		write!(w,"{}", match self {
 			Rator::Eq => "=",
-			Rator::Add => "+",
+			Rator::Add => "bvadd",
 			Rator::And => "and",
-			Rator::Div => "div",
-			Rator::GreaterEqual => ">=",
-			Rator::GreaterThan => ">",
-			Rator::LessEqual => "<=",
-			Rator::LessThan => "<",
-			Rator::Mod => "mod",
-			Rator::Mul => "*",
-			Rator::Neg => "-",
+			Rator::Or => "or",
+			Rator::Div => "bvudiv",
+			Rator::GreaterEqual => "bvuge",
+			Rator::GreaterThan => "bvugt",
+			Rator::LessEqual => "bvule",
+			Rator::LessThan => "bvult",
+			Rator::Mod => "bvurem",
+			Rator::Mul => "bvmul",
+			Rator::Neg => "bvneg",
 			Rator::Not => "not",
 			Rator::NotEqual => "(not (=",
-			Rator::Sub => "-",
+			Rator::Sub => "bvsub",
+			Rator::SignedDiv => "bvsdiv",
+			Rator::SignedMod => "bvsrem",
+			Rator::SignedGreaterEqual => "bvsge",
+			Rator::SignedGreaterThan => "bvsgt",
+			Rator::SignedLessEqual => "bvsle",
+			Rator::SignedLessThan => "bvslt",
+			Rator::BitNot => "bvnot",
+			Rator::Xor => "xor",
+			Rator::BitAnd => "bvand",
+			Rator::BitOr => "bvor",
+			Rator::BitXor => "bvxor",
+			Rator::ShiftLeft => "bvshl",
+			Rator::LogicalShiftRight => "bvlshr",
+			Rator::ArithmeticShiftRight => "bvashr",
+			Rator::ZeroExtend(n) => {write!(w,"(_ zero_extend {}) ", n)?; return Ok(())},
+			Rator::SignExtend(n) => {write!(w,"(_ sign_extend {}) ", n)?; return Ok(())},
+			Rator::Extract(hi, lo) => {write!(w,"(_ extract {} {}) ", hi, lo)?; return Ok(())},
 		});
 		write!(w," ");
 		Ok(())
@@ -408,8 +773,8 @@ impl rsmt2::print::Expr2Smt<()> for SymTy {
 					write!(w,"false ");
 				}
 			},
-			SymTy::Integer(num) => {
-				write!(w,"{} ",num);
+			SymTy::Integer(num, width) => {
+				write!(w,"(_ bv{} {}) ",width.mask(*num), width.bits);
 			}
 		}
 
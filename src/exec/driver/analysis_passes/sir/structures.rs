@@ -6,7 +6,7 @@ use std::io::Write;
 #[derive(Debug)]
 pub struct NodeVec<V>(Vec<V>);
 
-#[derive(Clone, Copy,Eq,PartialEq, Debug)]
+#[derive(Clone, Copy,Eq,PartialEq, Debug, RustcEncodable, RustcDecodable)]
 pub struct NodeId(usize);
 
 impl<V> ops::Index<NodeId> for NodeVec<V> {
@@ -78,7 +78,7 @@ impl <V> IntoIterator for &NameVec<V> {
 }
 
 
-#[derive(Hash,Clone, Copy,Eq,PartialEq, Debug)]
+#[derive(Hash,Clone, Copy,Eq,PartialEq, Debug, RustcEncodable, RustcDecodable)]
 pub struct Name(usize);
 
 
@@ -87,6 +87,10 @@ impl Name {
 		format!("x{}",self.0)
 	}
 
+	pub fn index(&self) -> usize {
+		self.0
+	}
+
 
 	pub fn from_str(val: &str) -> Name {
 		let id: usize;
@@ -0,0 +1,94 @@
+use super::{Sir,Declaration,Expr,Rator,Edge,MirVariableProp};
+use super::structures::{Name,NodeId};
+
+#[derive(Debug,Clone,RustcEncodable,RustcDecodable)]
+pub struct Summary {
+	declarations: Vec<Declaration>,
+	params: Vec<Name>,
+	returns: Vec<(Expr, Vec<Name>)>,
+	axioms: Vec<Expr>,
+	obligations: Vec<(Name, MirVariableProp, Expr)>,
+	touches_heap: bool
+}
+
+impl Summary {
+	pub fn from_sir(sir: &Sir, params: Vec<Name>, returns: &[(NodeId, Vec<Name>)], touches_heap: bool) -> Summary {
+		let declarations = sir.get_all_names().map(|nm| sir.get_declaration(nm).new_declaration()).collect();
+		let returns = returns.iter().map(|(nid, ret)| (sir.get_path_formula(*nid), ret.clone())).collect();
+
+		let mut obligations = Vec::new();
+		for nm in sir.get_all_names() {
+			for prop in sir.get_declaration(nm).get_property() {
+				obligations.push((nm, prop.clone(), sir.get_path_formula(prop.get_node())));
+			}
+		}
+
+		Summary {
+			declarations,
+			params,
+			returns,
+			axioms: sir.get_axioms().clone(),
+			obligations,
+			touches_heap
+		}
+	}
+
+	pub fn detached(&self) -> Summary {
+		Summary {
+			declarations: self.declarations.iter().map(|decl| decl.without_location()).collect(),
+			params: self.params.clone(),
+			returns: self.returns.clone(),
+			axioms: self.axioms.clone(),
+			obligations: self.obligations.clone(),
+			touches_heap: self.touches_heap
+		}
+	}
+
+	/*
+	 * Summaries only relate arguments to results, so a callee that reads or writes through a
+	 * pointer has to be executed in a frame sharing the caller's heap instead.
+	 */
+	pub fn touches_heap(&self) -> bool {
+		self.touches_heap
+	}
+
+	/*
+	 * Copies the summary into sir with fresh names, binding params to args at nid. Args and dest are
+	 * the flattened leaves of the call's operands and destination. Returns None, leaving sir as it
+	 * is, if there is not one arg for each param, as when the callee takes its arguments spread out
+	 * of a tuple. Otherwise returns the relation between dest and the callee's return paths, which
+	 * is None if the callee never returns.
+	 */
+	pub fn instantiate(&self, sir: &mut Sir, nid: NodeId, args: Vec<Expr>, dest: Vec<Name>) -> Option<Option<Expr>> {
+		if self.params.len() != args.len() {
+			return None;
+		}
+
+		let renamed: Vec<Name> = self.declarations.iter().map(|decl| sir.add_declaration(decl.new_declaration())).collect();
+		let rename = |nm: &Name| renamed[nm.index()];
+
+		for (param, arg) in self.params.iter().zip(args.into_iter()) {
+			sir.add_expr_to_node(nid, Expr::BinOp(Rator::Eq, Box::new(Expr::Ref(rename(param))), Box::new(arg)));
+		}
+
+		for axiom in self.axioms.iter() {
+			sir.add_axiom(axiom.rename(&rename));
+		}
+
+		for (nm, prop, guard) in self.obligations.iter() {
+			let side = sir.add_node();
+			sir.add_edge(nid, Edge::new(Some(guard.rename(&rename)), side));
+			sir.add_property_to_declaration(rename(nm), prop.rename(&rename).at_node(side));
+		}
+
+		Some(self.returns.iter().map(|(pc, ret)| {
+			dest.iter().zip(ret.iter()).fold(pc.rename(&rename), |acc, (dst, ret)| {
+				let bind = Expr::BinOp(Rator::Eq, Box::new(Expr::Ref(*dst)), Box::new(Expr::Ref(rename(ret))));
+				Expr::BinOp(Rator::And, Box::new(acc), Box::new(bind))
+			})
+		}).fold(None, |acc, path| Some(match acc {
+			Some(prev) => Expr::BinOp(Rator::Or, Box::new(prev), Box::new(path)),
+			None => path
+		})))
+	}
+}
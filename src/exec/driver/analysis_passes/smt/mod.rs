@@ -1,6 +1,4 @@
 
-use rustc::mir::Local;
-use rustc::hir::def_id::DefId;
 use rustc::session::config::Input;
 use rsmt2::parse::IdentParser;
 use rsmt2::parse::ModelParser;
@@ -11,7 +9,7 @@ use std::collections::HashMap;
 use rsmt2::errors::SmtRes;
 use rsmt2::print::Expr2Smt;
 
-use super::sir::{Rator,Expr,SymTy,Name};
+use super::sir::{Rator,Expr,SymTy,Name,BitWidth,VarLocation};
 
 
 
@@ -19,7 +17,7 @@ use super::sir::{Rator,Expr,SymTy,Name};
 use super::sir::MirVariableProp;
 
 
-pub fn solve_sir(sir: &Sir, entry: NodeId, additional_constraints: Vec<Expr>) -> Option<HashMap<(DefId,Local),SymTy>> {
+pub fn solve_sir(sir: &Sir, entry: NodeId, additional_constraints: Vec<Expr>) -> Option<HashMap<VarLocation,SymTy>> {
 	let mut solver =  Solver::default(SirParser).unwrap();
 
 	for name in sir.get_all_names() {
@@ -35,8 +33,9 @@ pub fn solve_sir(sir: &Sir, entry: NodeId, additional_constraints: Vec<Expr>) ->
 	
 	if solver.check_sat().unwrap() {
 		Some(solver.get_model().unwrap().into_iter().filter_map(|(name,_,_,val)| {
-			if let Some(loc) = sir.get_declaration(name).get_location() {
-				Some((loc.clone(),val))
+			let decl = sir.get_declaration(name);
+			if let Some(loc) = decl.get_location() {
+				Some((loc.clone(),val.with_sort(decl.get_type())))
 			} else {
 				None
 			}
@@ -52,13 +51,26 @@ struct SirParser;
 impl <'a> ModelParser<Name,SymTy,SymTy,&'a str> for SirParser {
 	fn parse_value(self, i: &'a str, _id: &Name, _pair: &[(Name, SymTy)], ty: &SymTy) -> SmtRes<SymTy> {
 		Ok(match ty {
-			SymTy::Integer(_) => SymTy::Integer(i.parse().unwrap()),
+			SymTy::Integer(_, width) => SymTy::Integer(parse_bit_vector(i), *width),
 			SymTy::Bool(_) => SymTy::Bool(i.parse().unwrap())
 
 		})
 	}
 }
 
+fn parse_bit_vector(i: &str) -> u128 {
+	if i.starts_with("#x") {
+		u128::from_str_radix(&i[2..], 16).unwrap()
+	} else if i.starts_with("#b") {
+		u128::from_str_radix(&i[2..], 2).unwrap()
+	} else {
+		let val: u128;
+		let _bits: u64;
+		scan!(i.bytes() => "(_ bv{} {})", val, _bits);
+		val
+	}
+}
+
 impl <'a> IdentParser<Name,SymTy,&'a str> for SirParser {
 	fn parse_ident(self, i: &'a str)  -> SmtRes<Name> {
 		Ok(Name::from_str(i))
@@ -66,9 +78,12 @@ impl <'a> IdentParser<Name,SymTy,&'a str> for SirParser {
 
 	fn parse_type(self, i: &'a str)  -> SmtRes<SymTy> {
 		Ok(match i {
-			"Int" => SymTy::Integer(0),
 			"Bool" => SymTy::Bool(false),
-			_ => panic!("oops")
+			_ => {
+				let bits: u64;
+				scan!(i.bytes() => "(_ BitVec {})", bits);
+				SymTy::Integer(0, BitWidth::new(bits, false))
+			}
 		})
 	}
 }
@@ -0,0 +1,355 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use rustc::hir::def_id::DefId;
+use rustc::mir::BinOp;
+use rustc::session::Session;
+use rustc::ty::{Ty,TyCtxt};
+use syntax::ast::{self,Attribute,ExprKind,LitKind,BinOpKind,UnOp};
+use syntax::parse::parse_expr_from_source_str;
+use syntax::print::pprust;
+use syntax::ptr::P;
+use syntax::visit::{self,Visitor};
+use syntax_pos::{FileName,Span};
+use crate::exec::driver::analysis_passes::sir::{Expr,Rator,SymTy,BitWidth};
+use super::memory::{Typing,Value};
+use super::binop_expr;
+
+
+const TOOL: &str = "rure";
+const REQUIRES: &str = "requires";
+const ENSURES: &str = "ensures";
+const RESULT: &str = "result";
+
+/*
+ * The #[rure::requires(..)] and #[rure::ensures(..)] clauses of a function, stated over its
+ * argument names, with ensures also naming the returned value as result.
+ */
+pub struct Contract {
+	args: Vec<String>,
+	requires: Vec<P<ast::Expr>>,
+	ensures: Vec<P<ast::Expr>>
+}
+
+impl Contract {
+	/*
+	 * The contract of did, or None if it has no clauses. A clause that does not parse as an
+	 * expression, or that names an argument bound by a pattern rather than by name, is warned about
+	 * and left out.
+	 */
+	pub fn of<'a,'tcx>(did: DefId, tcx: TyCtxt<'a,'tcx,'tcx>) -> Option<Contract> {
+		Contract::assuming(did, Vec::new(), tcx)
+	}
+
+	/*
+	 * The contract of did with the clauses in assumed added to what it requires.
+	 */
+	pub fn assuming<'a,'tcx>(did: DefId, assumed: Vec<P<ast::Expr>>, tcx: TyCtxt<'a,'tcx,'tcx>) -> Option<Contract> {
+		let attrs = tcx.get_attrs(did);
+		if assumed.is_empty() && !has_contract(&attrs) {
+			return None;
+		}
+
+		let args: Vec<String> = tcx.fn_arg_names(did).iter().map(|name| name.to_string()).collect();
+		let mut requires: Vec<_> = assumed.into_iter().filter(|clause| names_bound(clause, &args, false, clause.span, tcx)).collect();
+		let mut ensures = Vec::new();
+		for attr in attrs.iter() {
+			let (clauses, binds_result) = if is_clause(attr, REQUIRES) {
+				(&mut requires, false)
+			} else if is_clause(attr, ENSURES) {
+				(&mut ensures, true)
+			} else {
+				continue;
+			};
+
+			match attr.parse(&tcx.sess.parse_sess, |p| p.parse_expr()) {
+				Ok(expr) => if names_bound(&expr, &args, binds_result, attr.span, tcx) {
+					clauses.push(expr);
+				},
+				Err(mut err) => {
+					err.cancel();
+					tcx.sess.span_warn(attr.span, "rure cannot parse this contract clause, so it is ignored");
+				}
+			}
+		}
+
+		if requires.is_empty() && ensures.is_empty() {
+			return None;
+		}
+
+		Some(Contract {
+			args,
+			requires,
+			ensures
+		})
+	}
+
+	pub fn requires<'a,'tcx>(&self, args: Vec<(Value, Ty<'tcx>)>, typing: Typing<'a,'tcx>, tcx: TyCtxt<'a,'tcx,'tcx>) -> Expr {
+		let env = self.environment(args);
+		conjunction(&self.requires, &env, typing, tcx)
+	}
+
+	pub fn ensures<'a,'tcx>(&self, args: Vec<(Value, Ty<'tcx>)>, result: (Value, Ty<'tcx>), typing: Typing<'a,'tcx>, tcx: TyCtxt<'a,'tcx,'tcx>) -> Expr {
+		let mut env = self.environment(args);
+		env.insert(RESULT.to_owned(), result);
+		conjunction(&self.ensures, &env, typing, tcx)
+	}
+
+	fn environment<'tcx>(&self, args: Vec<(Value, Ty<'tcx>)>) -> HashMap<String, (Value, Ty<'tcx>)> {
+		self.args.iter().cloned().zip(args.into_iter()).collect()
+	}
+}
+
+/*
+ * Whether attrs declare a contract, which makes an unsafe fn worth verifying on its own.
+ */
+pub fn has_contract(attrs: &[Attribute]) -> bool {
+	attrs.iter().any(|attr| is_clause(attr, REQUIRES) || is_clause(attr, ENSURES))
+}
+
+/*
+ * The source of the contract clauses of did, so that results are not reused once they change.
+ */
+pub fn contract_source<'a,'tcx>(did: DefId, tcx: TyCtxt<'a,'tcx,'tcx>) -> Vec<String> {
+	tcx.get_attrs(did).iter().filter(|attr| is_clause(attr, REQUIRES) || is_clause(attr, ENSURES))
+		.map(|attr| pprust::attribute_to_string(attr)).collect()
+}
+
+/*
+ * The source of clauses, so that results are not reused once they change.
+ */
+pub fn clause_source(clauses: &[P<ast::Expr>]) -> Vec<String> {
+	clauses.iter().map(|clause| pprust::expr_to_string(clause)).collect()
+}
+
+/*
+ * Whether every name clause refers to is an argument bound by name, or the result when
+ * binds_result is set. Arguments bound by a pattern have no name of their own, so a clause over
+ * the names inside the pattern is warned about at span.
+ */
+fn names_bound<'a,'tcx>(clause: &ast::Expr, args: &[String], binds_result: bool, span: Span, tcx: TyCtxt<'a,'tcx,'tcx>) -> bool {
+	let mut names = Names(Vec::new());
+	names.visit_expr(clause);
+	match names.0.into_iter().find(|name| !args.contains(name) && !(binds_result && name == RESULT)) {
+		Some(name) => {
+			tcx.sess.span_warn(span, &format!("`{}` is not an argument bound by name, so rure ignores this contract clause", name));
+			false
+		},
+		None => true
+	}
+}
+
+/*
+ * The single-segment paths of an expression, which is how clauses refer to arguments.
+ */
+struct Names(Vec<String>);
+
+impl<'ast> Visitor<'ast> for Names {
+	fn visit_expr(&mut self, expr: &'ast ast::Expr) {
+		if let ExprKind::Path(None, path) = &expr.node {
+			if path.segments.len() == 1 {
+				self.0.push(path.segments[0].ident.to_string());
+			}
+		}
+		visit::walk_expr(self, expr);
+	}
+}
+
+fn is_clause(attr: &Attribute, kind: &str) -> bool {
+	let segments = &attr.path.segments;
+	segments.len() == 2 && &*segments[0].ident.as_str() == TOOL && &*segments[1].ident.as_str() == kind
+}
+
+/*
+ * Clauses read from an --rure-assume file, which constrain the arguments of a function while it is
+ * verified without being checked where it is called. Each line names a function by the path rustc
+ * prints for it and gives a clause over its arguments, as in `Buffer::get_raw index < 64`. Blank
+ * lines and lines starting with # are skipped.
+ */
+pub struct Assumptions {
+	clauses: HashMap<String, Vec<P<ast::Expr>>>
+}
+
+impl Assumptions {
+	pub fn new() -> Assumptions {
+		Assumptions {
+			clauses: HashMap::new()
+		}
+	}
+
+	pub fn load(path: &Path, sess: &Session) -> Assumptions {
+		let mut assumptions = Assumptions::new();
+		let contents = match fs::read_to_string(path) {
+			Ok(contents) => contents,
+			Err(err) => {
+				sess.err(&format!("could not read assumptions from {}: {}", path.display(), err));
+				return assumptions;
+			}
+		};
+
+		for (line_no, line) in contents.lines().enumerate() {
+			let line = line.trim();
+			if line.is_empty() || line.starts_with('#') {
+				continue;
+			}
+
+			let mut parts = line.splitn(2, char::is_whitespace);
+			let (func, clause) = match (parts.next(), parts.next()) {
+				(Some(func), Some(clause)) => (func, clause),
+				_ => {
+					sess.err(&format!("{}:{}: expected a function path followed by a clause", path.display(), line_no + 1));
+					continue;
+				}
+			};
+
+			let name = FileName::Custom(format!("{}:{}", path.display(), line_no + 1));
+			match parse_expr_from_source_str(name, clause.to_owned(), &sess.parse_sess) {
+				Ok(expr) => assumptions.clauses.entry(func.to_owned()).or_insert_with(Vec::new).push(expr),
+				Err(mut err) => err.emit()
+			}
+		}
+
+		assumptions
+	}
+
+	pub fn of<'a,'tcx>(&self, did: DefId, tcx: TyCtxt<'a,'tcx,'tcx>) -> Vec<P<ast::Expr>> {
+		self.clauses.get(&tcx.def_path_str(did)).cloned().unwrap_or_default()
+	}
+}
+
+/*
+ * The conjunction of the clauses that can be translated. The rest are warned about and left out,
+ * so that a contract is never stronger than what it states.
+ */
+fn conjunction<'a,'tcx>(clauses: &Vec<P<ast::Expr>>, env: &HashMap<String, (Value, Ty<'tcx>)>, typing: Typing<'a,'tcx>, tcx: TyCtxt<'a,'tcx,'tcx>) -> Expr {
+	clauses.iter().filter_map(|clause| match translate(clause, env, typing, tcx) {
+		Some(Term::Typed(Value::Scalar(expr), ty)) if ty.is_bool() => Some(expr),
+		_ => {
+			tcx.sess.span_warn(clause.span, "rure cannot check this contract clause, so it is ignored");
+			None
+		}
+	}).fold(Expr::Value(SymTy::from_boolean(true)), |x, y| Expr::BinOp(Rator::And, Box::new(x), Box::new(y)))
+}
+
+/*
+ * Integer literals take the type of the operand they are compared or combined with.
+ */
+enum Term<'tcx> {
+	Typed(Value, Ty<'tcx>),
+	Literal(u128)
+}
+
+/*
+ * Clauses are built from arguments, integer and boolean literals, arithmetic, comparisons and the
+ * logical operators, along with is_null() on pointers and len() on slices. Arithmetic wraps, as
+ * it does in the MIR it is checked against. As in Rust, both operands of a binary operator must
+ * have the same type, except for the amount of a shift, and a literal must fit the type it takes.
+ */
+fn translate<'a,'tcx>(expr: &ast::Expr, env: &HashMap<String, (Value, Ty<'tcx>)>, typing: Typing<'a,'tcx>, tcx: TyCtxt<'a,'tcx,'tcx>) -> Option<Term<'tcx>> {
+	match &expr.node {
+		ExprKind::Paren(inner) => translate(inner, env, typing, tcx),
+		ExprKind::Lit(lit) => match lit.node {
+			LitKind::Int(val, _) => Some(Term::Literal(val)),
+			LitKind::Bool(val) => Some(Term::Typed(Value::Scalar(Expr::Value(SymTy::from_boolean(val))), tcx.types.bool)),
+			_ => None
+		},
+		ExprKind::Path(None, path) if path.segments.len() == 1 => {
+			let (val, ty) = env.get(&*path.segments[0].ident.as_str())?.clone();
+			Some(Term::Typed(val, ty))
+		},
+		ExprKind::MethodCall(method, args) if args.len() == 1 => {
+			let (val, ty) = typed(translate(&args[0], env, typing, tcx)?)?;
+			match &*method.ident.as_str() {
+				"is_null" if typing.is_pointer(ty) => {
+					let null = Expr::BinOp(Rator::Eq, Box::new(scalar(val)?), Box::new(Expr::Value(SymTy::pointer(0))));
+					Some(Term::Typed(Value::Scalar(null), tcx.types.bool))
+				},
+				"len" => val.length().map(|len| Term::Typed(Value::Scalar(len), tcx.types.usize)),
+				_ => None
+			}
+		},
+		ExprKind::Unary(UnOp::Not, inner) => {
+			let (val, ty) = typed(translate(inner, env, typing, tcx)?)?;
+			if ty.is_bool() {
+				Some(Term::Typed(Value::Scalar(Expr::UnOp(Rator::Not, Box::new(scalar(val)?))), ty))
+			} else {
+				None
+			}
+		},
+		ExprKind::Binary(op, lhs, rhs) => {
+			let (lhs, rhs) = (translate(lhs, env, typing, tcx)?, translate(rhs, env, typing, tcx)?);
+			let ((lhs, lhs_ty), (rhs, rhs_ty)) = match (lhs, rhs) {
+				(Term::Typed(lhs, lhs_ty), Term::Typed(rhs, rhs_ty)) => ((scalar(lhs)?, lhs_ty), (scalar(rhs)?, rhs_ty)),
+				(Term::Typed(lhs, ty), Term::Literal(rhs)) => ((scalar(lhs)?, ty), (literal(rhs, ty)?, ty)),
+				(Term::Literal(lhs), Term::Typed(rhs, ty)) => ((literal(lhs, ty)?, ty), (scalar(rhs)?, ty)),
+				(Term::Literal(lhs), Term::Literal(rhs)) => {
+					let ty = tcx.types.usize;
+					((literal(lhs, ty)?, ty), (literal(rhs, ty)?, ty))
+				}
+			};
+
+			let integral = BitWidth::of(lhs_ty).is_some() && BitWidth::of(rhs_ty).is_some();
+			let shift = op.node == BinOpKind::Shl || op.node == BinOpKind::Shr;
+			if lhs_ty != rhs_ty && !(shift && integral) {
+				return None;
+			}
+			let operable = match op.node {
+				BinOpKind::And | BinOpKind::Or | BinOpKind::Eq | BinOpKind::Ne => true,
+				BinOpKind::BitAnd | BinOpKind::BitOr | BinOpKind::BitXor => integral || lhs_ty.is_bool(),
+				_ => integral
+			};
+			if !operable {
+				return None;
+			}
+
+			let (op, ty) = match op.node {
+				BinOpKind::And | BinOpKind::Or if lhs_ty.is_bool() && rhs_ty.is_bool() => {
+					let rator = if op.node == BinOpKind::And { Rator::And } else { Rator::Or };
+					return Some(Term::Typed(Value::Scalar(Expr::BinOp(rator, Box::new(lhs), Box::new(rhs))), tcx.types.bool));
+				},
+				BinOpKind::And | BinOpKind::Or => return None,
+				BinOpKind::Add => (BinOp::Add, lhs_ty),
+				BinOpKind::Sub => (BinOp::Sub, lhs_ty),
+				BinOpKind::Mul => (BinOp::Mul, lhs_ty),
+				BinOpKind::Div => (BinOp::Div, lhs_ty),
+				BinOpKind::Rem => (BinOp::Rem, lhs_ty),
+				BinOpKind::BitAnd => (BinOp::BitAnd, lhs_ty),
+				BinOpKind::BitOr => (BinOp::BitOr, lhs_ty),
+				BinOpKind::BitXor => (BinOp::BitXor, lhs_ty),
+				BinOpKind::Shl => (BinOp::Shl, lhs_ty),
+				BinOpKind::Shr => (BinOp::Shr, lhs_ty),
+				BinOpKind::Eq => (BinOp::Eq, tcx.types.bool),
+				BinOpKind::Ne => (BinOp::Ne, tcx.types.bool),
+				BinOpKind::Lt => (BinOp::Lt, tcx.types.bool),
+				BinOpKind::Le => (BinOp::Le, tcx.types.bool),
+				BinOpKind::Gt => (BinOp::Gt, tcx.types.bool),
+				BinOpKind::Ge => (BinOp::Ge, tcx.types.bool)
+			};
+			Some(Term::Typed(Value::Scalar(binop_expr(op, lhs, rhs, lhs_ty, rhs_ty, typing)), ty))
+		},
+		_ => None
+	}
+}
+
+fn typed<'tcx>(term: Term<'tcx>) -> Option<(Value, Ty<'tcx>)> {
+	match term {
+		Term::Typed(val, ty) => Some((val, ty)),
+		Term::Literal(_) => None
+	}
+}
+
+fn scalar(val: Value) -> Option<Expr> {
+	match val {
+		Value::Scalar(expr) | Value::Pointer(expr, _) | Value::Slice(expr, _, _) => Some(expr),
+		_ => None
+	}
+}
+
+fn literal<'tcx>(val: u128, ty: Ty<'tcx>) -> Option<Expr> {
+	let width = BitWidth::of(ty)?;
+	let bits = if width.is_signed() { width.get_bits() - 1 } else { width.get_bits() };
+	if bits < 128 && val >> bits != 0 {
+		return None;
+	}
+	Some(Expr::Value(SymTy::from_scalar(val, ty)))
+}
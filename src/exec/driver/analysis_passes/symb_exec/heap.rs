@@ -0,0 +1,643 @@
+use rustc::ty::{Ty,TyKind,AdtDef};
+use rustc::hir::def_id::DefId;
+use std::collections::HashMap;
+use crate::exec::driver::analysis_passes::sir::{Sir,NodeId,Declaration,Expr,Rator,SymTy,BitWidth,ObligationKind};
+use super::memory::{Typing,Value,one_of,non_null,non_zero,aligned,fresh_value,report,name_of};
+
+
+/*
+ * Bools are kept in memory as the byte they are laid out as, so that any byte can be read back as
+ * one.
+ */
+fn byte_sort() -> SymTy {
+	SymTy::Integer(0, BitWidth::new(8, false))
+}
+
+fn byte(val: u128) -> Expr {
+	Expr::Value(SymTy::Integer(val, BitWidth::new(8, false)))
+}
+
+/*
+ * Fieldless enums with an integer repr keep their discriminant in the data at their address, so
+ * that an integer reinterpreted as such an enum is read back as its discriminant.
+ */
+fn discriminant_kind(def: &AdtDef) -> CellKind {
+	if def.repr.int.is_some() && def.variants.iter().all(|variant| variant.fields.is_empty()) {
+		CellKind::Data
+	} else {
+		CellKind::Discriminant
+	}
+}
+
+pub fn offset(addr: Expr, bytes: u64) -> Expr {
+	if bytes == 0 {
+		addr
+	} else {
+		Expr::BinOp(Rator::Add, Box::new(addr), Box::new(Expr::Value(SymTy::pointer(bytes as u128))))
+	}
+}
+
+/*
+ * The allocation a pointer was derived from. A base of zero stands for an allocation that is not
+ * known, such as the one an argument points into. The tag names the borrow the pointer was derived
+ * from, zero standing for the allocation itself. A pointer may only access the limit bytes at
+ * offset into its allocation, which is all of it unless the pointer was taken out of a slice. Only
+ * the accesses of pointers into a known allocation or a slice are reported as out of bounds.
+ */
+#[derive(Clone,Debug)]
+pub struct Provenance {
+	base: Expr,
+	size: Expr,
+	offset: Expr,
+	limit: Expr,
+	tag: Expr
+}
+
+impl Provenance {
+	pub fn new(base: Expr, size: Expr) -> Provenance {
+		Provenance {
+			base,
+			size: size.clone(),
+			offset: Expr::Value(SymTy::pointer(0)),
+			limit: size,
+			tag: Expr::Value(SymTy::pointer(0))
+		}
+	}
+
+	/*
+	 * Narrows the bytes this pointer may access to the limit bytes at offset into its allocation.
+	 */
+	pub fn within(self, offset: Expr, limit: Expr) -> Provenance {
+		Provenance {
+			offset,
+			limit,
+			..self
+		}
+	}
+
+	/*
+	 * Narrows the bytes this pointer may access to the len bytes at addr.
+	 */
+	pub fn within_range(self, addr: Expr, len: Expr) -> Provenance {
+		let offset = Expr::BinOp(Rator::Sub, Box::new(addr), Box::new(self.base()));
+		self.within(offset, len)
+	}
+
+	pub fn with_tag(self, tag: Expr) -> Provenance {
+		Provenance {
+			tag,
+			..self
+		}
+	}
+
+	pub fn unknown() -> Provenance {
+		Provenance::new(Expr::Value(SymTy::pointer(0)), Expr::Value(SymTy::pointer(0)))
+	}
+
+	pub fn base(&self) -> Expr {
+		self.base.clone()
+	}
+
+	pub fn size(&self) -> Expr {
+		self.size.clone()
+	}
+
+	pub fn offset(&self) -> Expr {
+		self.offset.clone()
+	}
+
+	pub fn limit(&self) -> Expr {
+		self.limit.clone()
+	}
+
+	pub fn tag(&self) -> Expr {
+		self.tag.clone()
+	}
+
+	/*
+	 * Holds when accessing len bytes at addr reaches outside of the bytes this pointer may access.
+	 */
+	pub fn out_of_bounds(&self, addr: &Expr, len: u64) -> Expr {
+		self.exceeds(addr, Expr::Value(SymTy::pointer(len as u128)))
+	}
+
+	/*
+	 * Like out_of_bounds, for a number of bytes that is not known.
+	 */
+	pub fn exceeds(&self, addr: &Expr, len: Expr) -> Expr {
+		let end = Expr::BinOp(Rator::Add, Box::new(addr.clone()), Box::new(len));
+		let start = Expr::BinOp(Rator::Add, Box::new(self.base()), Box::new(self.offset()));
+		let limit = Expr::BinOp(Rator::Add, Box::new(start.clone()), Box::new(self.limit()));
+		let inside = vec![
+			Expr::BinOp(Rator::LessEqual, Box::new(start.clone()), Box::new(addr.clone())),
+			Expr::BinOp(Rator::LessEqual, Box::new(addr.clone()), Box::new(end.clone())),
+			Expr::BinOp(Rator::LessEqual, Box::new(end), Box::new(limit))
+		].into_iter().fold(Expr::Value(SymTy::from_boolean(true)), |x, y| Expr::BinOp(Rator::And, Box::new(x), Box::new(y)));
+		Expr::BinOp(Rator::And, Box::new(non_null(start)), Box::new(Expr::UnOp(Rator::Not, Box::new(inside))))
+	}
+}
+
+/*
+ * What a cell holds. Discriminants are kept apart from the data of their enum so that niche
+ * encodings need not be modelled, and the provenance of a stored pointer and the length of a stored
+ * slice are kept beside it.
+ */
+#[derive(Clone,Copy,PartialEq)]
+enum CellKind {
+	Data,
+	Discriminant,
+	Base,
+	Size,
+	Offset,
+	Limit,
+	Tag,
+	Length
+}
+
+/*
+ * What a borrow may do with the memory it covers. Shared references to memory without interior
+ * mutability are read only, while raw pointers, two-phase borrows and shared references to
+ * interior mutability may write without being unique.
+ */
+#[derive(Clone,Copy,PartialEq)]
+pub enum Permission {
+	Unique,
+	SharedReadOnly,
+	SharedReadWrite
+}
+
+/*
+ * A reference or raw pointer created from the size bytes at addr, in the allocation at base. It is
+ * popped whenever popped holds, after which using its tag is undefined.
+ */
+#[derive(Clone)]
+struct Borrow {
+	tag: u64,
+	base: Expr,
+	addr: Expr,
+	size: u64,
+	perm: Permission,
+	popped: Expr
+}
+
+/*
+ * A scalar stored at an address. Cells of different sorts or kinds never alias.
+ */
+#[derive(Clone)]
+struct Cell {
+	addr: Expr,
+	sort: SymTy,
+	kind: CellKind,
+	val: Expr
+}
+
+impl Cell {
+	fn matches(&self, sort: &SymTy, kind: CellKind) -> bool {
+		self.sort == *sort && self.kind == kind
+	}
+
+	fn at(&self, addr: &Expr) -> Expr {
+		Expr::BinOp(Rator::Eq, Box::new(addr.clone()), Box::new(self.addr.clone()))
+	}
+}
+
+/*
+ * Memory reachable through pointers, kept as a log of stores. A load is an ite chain over the
+ * stores that may alias it, falling back to the value the cell held before the log started. Those
+ * initial values are shared between loads of equal addresses so that two loads with no store in
+ * between agree.
+ */
+#[derive(Clone)]
+pub struct Heap {
+	stores: Vec<Cell>,
+	initial: Vec<Cell>,
+	allocations: Vec<(Expr, u64)>,
+	freed: Vec<(Expr, Expr, Expr)>,
+	uninit: Vec<(Expr, u64, usize)>,
+	borrows: Vec<Borrow>,
+	read_only: Vec<Expr>,
+	statics: HashMap<DefId, Expr>,
+	accessed: bool
+}
+
+impl Heap {
+	pub fn new() -> Heap {
+		Heap {
+			stores: Vec::new(),
+			initial: Vec::new(),
+			allocations: Vec::new(),
+			freed: Vec::new(),
+			uninit: Vec::new(),
+			borrows: Vec::new(),
+			read_only: Vec::new(),
+			statics: HashMap::new(),
+			accessed: false
+		}
+	}
+
+	pub fn mark_accessed(&mut self) {
+		self.accessed = true;
+	}
+
+	pub fn is_accessed(&self) -> bool {
+		self.accessed
+	}
+
+	/*
+	 * Forgets the contents of the heap, as after a call that may write through any pointer it was
+	 * given. Allocations stay live.
+	 */
+	pub fn havoc(&mut self) {
+		self.stores.clear();
+		self.initial.clear();
+		self.uninit.clear();
+		self.accessed = true;
+	}
+
+	/*
+	 * Marks the size bytes at addr as uninitialized. They stay so until something is stored at
+	 * them.
+	 */
+	pub fn deinit(&mut self, addr: Expr, size: u64) {
+		self.uninit.push((addr, size, self.stores.len()));
+	}
+
+	/*
+	 * Holds when addr lies in uninitialized memory that nothing has been stored at since, or None if
+	 * no memory is uninitialized. Only stores at addr itself count, whatever their size.
+	 */
+	fn uninit_at(&self, addr: &Expr) -> Option<Expr> {
+		self.uninit.iter().map(|(base, size, since)| {
+			let end = offset(base.clone(), *size);
+			let inside = Expr::BinOp(Rator::And, Box::new(Expr::BinOp(Rator::LessEqual, Box::new(base.clone()), Box::new(addr.clone()))), Box::new(Expr::BinOp(Rator::LessThan, Box::new(addr.clone()), Box::new(end))));
+			self.stores[*since..].iter().map(|cell| Expr::UnOp(Rator::Not, Box::new(cell.at(addr))))
+				.fold(inside, |x, y| Expr::BinOp(Rator::And, Box::new(x), Box::new(y)))
+		}).fold(None, |acc, uninit| Some(match acc {
+			Some(prev) => Expr::BinOp(Rator::Or, Box::new(prev), Box::new(uninit)),
+			None => uninit
+		}))
+	}
+
+	fn check_init(&self, addr: &Expr, nid: NodeId, sir: &mut Sir) {
+		if let Some(violation) = self.uninit_at(addr) {
+			report(violation, ObligationKind::UninitRead, nid, sir);
+		}
+	}
+
+	/*
+	 * A fresh non-null, aligned base address for size bytes that does not overlap any earlier
+	 * allocation.
+	 */
+	pub fn allocate(&mut self, size: u64, align: u64, nid: NodeId, sir: &mut Sir) -> Expr {
+		let base = Expr::Ref(sir.add_declaration(Declaration::of_sort(SymTy::pointer(0))));
+		let end = offset(base.clone(), size);
+		sir.add_expr_to_node(nid, non_null(base.clone()));
+		sir.add_expr_to_node(nid, Expr::BinOp(Rator::LessEqual, Box::new(base.clone()), Box::new(end.clone())));
+		if align > 1 {
+			sir.add_expr_to_node(nid, aligned(base.clone(), align));
+		}
+
+		for (other, other_size) in self.allocations.iter() {
+			let before = Expr::BinOp(Rator::LessEqual, Box::new(end.clone()), Box::new(other.clone()));
+			let after = Expr::BinOp(Rator::LessEqual, Box::new(offset(other.clone(), *other_size)), Box::new(base.clone()));
+			sir.add_expr_to_node(nid, Expr::BinOp(Rator::Or, Box::new(before), Box::new(after)));
+		}
+
+		self.allocations.push((base.clone(), size));
+		base
+	}
+
+	/*
+	 * Frees the size bytes at base whenever cond holds. Freed memory is never handed out again, as
+	 * allocations stay disjoint from every earlier one.
+	 */
+	pub fn free(&mut self, cond: Expr, base: Expr, size: Expr) {
+		self.freed.push((cond, base, size));
+		self.accessed = true;
+	}
+
+	/*
+	 * Holds when addr lies in memory that has been freed, or None if nothing has been freed.
+	 */
+	pub fn freed_at(&self, addr: &Expr) -> Option<Expr> {
+		self.freed.iter().map(|(cond, base, size)| {
+			let end = Expr::BinOp(Rator::Add, Box::new(base.clone()), Box::new(size.clone()));
+			let inside = Expr::BinOp(Rator::And, Box::new(Expr::BinOp(Rator::LessEqual, Box::new(base.clone()), Box::new(addr.clone()))), Box::new(Expr::BinOp(Rator::LessThan, Box::new(addr.clone()), Box::new(end))));
+			Expr::BinOp(Rator::And, Box::new(cond.clone()), Box::new(inside))
+		}).fold(None, |acc, freed| Some(match acc {
+			Some(prev) => Expr::BinOp(Rator::Or, Box::new(prev), Box::new(freed)),
+			None => freed
+		}))
+	}
+
+	/*
+	 * Holds when addr is the start of memory that has been freed, or None if nothing has been freed.
+	 */
+	pub fn freed_base(&self, addr: &Expr) -> Option<Expr> {
+		self.freed.iter().map(|(cond, base, _)| {
+			Expr::BinOp(Rator::And, Box::new(cond.clone()), Box::new(Expr::BinOp(Rator::Eq, Box::new(base.clone()), Box::new(addr.clone()))))
+		}).fold(None, |acc, freed| Some(match acc {
+			Some(prev) => Expr::BinOp(Rator::Or, Box::new(prev), Box::new(freed)),
+			None => freed
+		}))
+	}
+
+	/*
+	 * Makes the allocation at base read-only, as the memory of an immutable static or a constant.
+	 */
+	pub fn protect(&mut self, base: Expr) {
+		self.read_only.push(base);
+	}
+
+	/*
+	 * The base address of the static did, allocated by allocate on first use.
+	 */
+	pub fn static_base(&mut self, did: DefId, allocate: impl FnOnce(&mut Heap) -> Expr) -> Expr {
+		if let Some(base) = self.statics.get(&did) {
+			return base.clone();
+		}
+		let base = allocate(self);
+		self.statics.insert(did, base.clone());
+		base
+	}
+
+	/*
+	 * Writing through a pointer of provenance prov must neither reach a read-only allocation nor go
+	 * through a shared reference to memory without interior mutability.
+	 */
+	fn check_writable(&self, prov: &Provenance, nid: NodeId, sir: &mut Sir) {
+		let through_shared = self.borrows.iter().filter(|borrow| borrow.perm == Permission::SharedReadOnly)
+			.map(|borrow| Expr::BinOp(Rator::Eq, Box::new(prov.tag()), Box::new(Expr::Value(SymTy::pointer(borrow.tag as u128)))));
+		let into_read_only = self.read_only.iter().map(|base| Expr::BinOp(Rator::Eq, Box::new(prov.base()), Box::new(base.clone())));
+		let violation = through_shared.chain(into_read_only).fold(None, |acc, write| Some(match acc {
+			Some(prev) => Expr::BinOp(Rator::Or, Box::new(prev), Box::new(write)),
+			None => write
+		}));
+		if let Some(violation) = violation {
+			report(violation, ObligationKind::ReadOnlyWrite, nid, sir);
+		}
+	}
+
+	/*
+	 * Accesses the size bytes at addr through a pointer of provenance prov, in the manner of Stacked
+	 * Borrows. Using a popped tag is reported. Every borrow of the same bytes created after the one
+	 * the access goes through is popped by a write, and so is every unique one by a read. Only
+	 * borrows of allocations that are known are tracked.
+	 */
+	pub fn access(&mut self, prov: &Provenance, addr: &Expr, size: u64, write: bool, nid: NodeId, sir: &mut Sir) {
+		if size == 0 {
+			return;
+		}
+		if write {
+			self.check_writable(prov, nid, sir);
+		}
+		if self.borrows.is_empty() {
+			return;
+		}
+
+		let through = |tag: u64| Expr::BinOp(Rator::Eq, Box::new(prov.tag()), Box::new(Expr::Value(SymTy::pointer(tag as u128))));
+		let violation = self.borrows.iter().map(|borrow| Expr::BinOp(Rator::And, Box::new(through(borrow.tag)), Box::new(borrow.popped.clone())))
+			.fold(Expr::Value(SymTy::from_boolean(false)), |x, y| Expr::BinOp(Rator::Or, Box::new(x), Box::new(y)));
+		report(violation, ObligationKind::AliasingViolation, nid, sir);
+
+		let mut below = through(0);
+		for i in 0..self.borrows.len() {
+			let borrow = &self.borrows[i];
+			if write || borrow.perm == Permission::Unique {
+				let overlaps = vec![
+					non_null(borrow.base.clone()),
+					Expr::BinOp(Rator::Eq, Box::new(borrow.base.clone()), Box::new(prov.base())),
+					Expr::BinOp(Rator::LessThan, Box::new(borrow.addr.clone()), Box::new(offset(addr.clone(), size))),
+					Expr::BinOp(Rator::LessThan, Box::new(addr.clone()), Box::new(offset(borrow.addr.clone(), borrow.size)))
+				].into_iter().fold(below.clone(), |x, y| Expr::BinOp(Rator::And, Box::new(x), Box::new(y)));
+				let popped = Expr::BinOp(Rator::Or, Box::new(borrow.popped.clone()), Box::new(overlaps));
+				self.borrows[i].popped = Expr::Ref(name_of(popped, SymTy::from_boolean(false), nid, sir));
+			}
+			below = Expr::BinOp(Rator::Or, Box::new(below), Box::new(through(self.borrows[i].tag)));
+		}
+	}
+
+	/*
+	 * Creates a borrow with permission perm of the size bytes at addr from a pointer of provenance
+	 * prov, which accesses them, and returns the provenance of the new pointer.
+	 */
+	pub fn reborrow(&mut self, prov: Provenance, addr: Expr, size: u64, perm: Permission, nid: NodeId, sir: &mut Sir) -> Provenance {
+		self.access(&prov, &addr, size, perm == Permission::Unique, nid, sir);
+		let tag = self.borrows.len() as u64 + 1;
+		self.borrows.push(Borrow {
+			tag,
+			base: prov.base(),
+			addr,
+			size,
+			perm,
+			popped: Expr::Value(SymTy::from_boolean(false))
+		});
+		prov.with_tag(Expr::Value(SymTy::pointer(tag as u128)))
+	}
+
+	/*
+	 * Pointers that were in memory before the log started point into allocations that are not
+	 * known.
+	 */
+	fn load(&mut self, addr: Expr, sort: SymTy, kind: CellKind, sir: &mut Sir) -> Expr {
+		let fresh = Expr::Ref(sir.add_declaration(Declaration::of_sort(sort.clone())));
+		if kind == CellKind::Base || kind == CellKind::Offset || kind == CellKind::Tag {
+			sir.add_axiom(Expr::BinOp(Rator::Eq, Box::new(fresh.clone()), Box::new(Expr::Value(SymTy::pointer(0)))));
+		}
+
+		let mut val = fresh.clone();
+		for cell in self.initial.iter().rev().filter(|cell| cell.matches(&sort, kind)) {
+			val = Expr::Ite(Box::new(cell.at(&addr)), Box::new(cell.val.clone()), Box::new(val));
+		}
+
+		for cell in self.stores.iter().filter(|cell| cell.matches(&sort, kind)) {
+			val = Expr::Ite(Box::new(cell.at(&addr)), Box::new(cell.val.clone()), Box::new(val));
+		}
+
+		self.initial.push(Cell {
+			addr,
+			sort,
+			kind,
+			val: fresh
+		});
+		val
+	}
+
+	/*
+	 * Loads the scalar of sort at addr, reporting it whenever valid does not hold of it. Memory that
+	 * was never written is assumed to hold a valid value.
+	 */
+	fn load_valid(&mut self, addr: Expr, sort: SymTy, kind: CellKind, valid: impl Fn(Expr) -> Expr, nid: NodeId, sir: &mut Sir) -> Expr {
+		let val = self.load(addr, sort, kind, sir);
+		let fresh = self.initial.last().unwrap().val.clone();
+		sir.add_axiom(valid(fresh));
+		report(Expr::UnOp(Rator::Not, Box::new(valid(val.clone()))), ObligationKind::InvalidValue, nid, sir);
+		val
+	}
+
+	fn store(&mut self, addr: Expr, sort: SymTy, kind: CellKind, val: Expr) {
+		self.stores.push(Cell {
+			addr,
+			sort,
+			kind,
+			val
+		});
+	}
+
+	fn load_pointer(&mut self, addr: Expr, fat: bool, non_null_ptr: bool, nid: NodeId, sir: &mut Sir) -> Value {
+		let ptr = if non_null_ptr {
+			self.load_valid(addr.clone(), SymTy::pointer(0), CellKind::Data, non_null, nid, sir)
+		} else {
+			self.load(addr.clone(), SymTy::pointer(0), CellKind::Data, sir)
+		};
+		let base = self.load(addr.clone(), SymTy::pointer(0), CellKind::Base, sir);
+		let size = self.load(addr.clone(), SymTy::pointer(0), CellKind::Size, sir);
+		let offset = self.load(addr.clone(), SymTy::pointer(0), CellKind::Offset, sir);
+		let limit = self.load(addr.clone(), SymTy::pointer(0), CellKind::Limit, sir);
+		let tag = self.load(addr.clone(), SymTy::pointer(0), CellKind::Tag, sir);
+		let prov = Provenance::new(base, size).within(offset, limit).with_tag(tag);
+		if fat {
+			let len = self.load(addr, SymTy::pointer(0), CellKind::Length, sir);
+			Value::Slice(ptr, len, prov)
+		} else {
+			Value::Pointer(ptr, prov)
+		}
+	}
+
+	fn store_pointer(&mut self, addr: Expr, val: Value, fat: bool, sir: &mut Sir) {
+		let (prov, len) = (val.provenance(), val.length());
+		let ptr = val.scalar().unwrap_or_else(|| Expr::Ref(sir.add_declaration(Declaration::of_sort(SymTy::pointer(0)))));
+		self.store(addr.clone(), SymTy::pointer(0), CellKind::Data, ptr);
+		self.store(addr.clone(), SymTy::pointer(0), CellKind::Base, prov.base);
+		self.store(addr.clone(), SymTy::pointer(0), CellKind::Size, prov.size);
+		self.store(addr.clone(), SymTy::pointer(0), CellKind::Offset, prov.offset);
+		self.store(addr.clone(), SymTy::pointer(0), CellKind::Limit, prov.limit);
+		self.store(addr.clone(), SymTy::pointer(0), CellKind::Tag, prov.tag);
+		if fat {
+			let len = len.unwrap_or_else(|| Expr::Ref(sir.add_declaration(Declaration::of_sort(SymTy::pointer(0)))));
+			self.store(addr, SymTy::pointer(0), CellKind::Length, len);
+		}
+	}
+
+	pub fn load_discriminant<'a,'tcx>(&mut self, addr: Expr, def: &'tcx AdtDef, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) -> Expr {
+		let discriminants = typing.discriminants(def);
+		self.check_init(&addr, nid, sir);
+		self.load_valid(addr, typing.sort_of(typing.discr_ty(def)).unwrap(), discriminant_kind(def), |discr| one_of(discr, &discriminants), nid, sir)
+	}
+
+	pub fn store_discriminant<'a,'tcx>(&mut self, addr: Expr, def: &'tcx AdtDef, val: Expr, typing: Typing<'a,'tcx>) {
+		self.store(addr, typing.sort_of(typing.discr_ty(def)).unwrap(), discriminant_kind(def), val);
+	}
+
+	pub fn load_value<'a,'tcx>(&mut self, addr: Expr, ty: Ty<'tcx>, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) -> Value {
+		if typing.is_uninhabited(ty) {
+			report(Expr::Value(SymTy::from_boolean(true)), ObligationKind::InvalidValue, nid, sir);
+			return Value::Opaque;
+		}
+
+		if typing.is_pointer(ty) || ty.is_bool() || typing.sort_of(ty).is_some() || typing.is_non_zero(ty) {
+			self.check_init(&addr, nid, sir);
+		}
+
+		if typing.is_pointer(ty) {
+			return self.load_pointer(addr, typing.is_fat(ty), typing.is_non_null(ty), nid, sir);
+		}
+
+		if ty.is_bool() {
+			let val = self.load_valid(addr, byte_sort(), CellKind::Data, |val| Expr::BinOp(Rator::LessEqual, Box::new(val), Box::new(byte(1))), nid, sir);
+			return Value::Scalar(Expr::BinOp(Rator::Eq, Box::new(val), Box::new(byte(1))));
+		}
+
+		if let Some(sort) = typing.sort_of(ty) {
+			return Value::Scalar(match typing.validity(ty) {
+				Some(valid) => self.load_valid(addr, sort, CellKind::Data, valid, nid, sir),
+				None => self.load(addr, sort, CellKind::Data, sir)
+			});
+		}
+
+		if let (true, Some(tys)) = (typing.is_non_zero(ty), typing.field_tys(ty)) {
+			let field = match (typing.is_pointer(tys[0]), typing.sort_of(tys[0])) {
+				(true, _) => self.load_pointer(addr, typing.is_fat(tys[0]), true, nid, sir),
+				(false, Some(sort)) => Value::Scalar(self.load_valid(addr, sort.clone(), CellKind::Data, |val| non_zero(val, &sort), nid, sir)),
+				(false, None) => self.load_value(addr, tys[0], nid, typing, sir)
+			};
+			return Value::Aggregate(vec![field]);
+		}
+
+		match ty.sty {
+			TyKind::Adt(def, substs) if def.is_enum() => {
+				let discr = self.load_discriminant(addr.clone(), def, nid, typing, sir);
+				let mut variants = Vec::new();
+				for (idx, variant) in def.variants.iter_enumerated() {
+					let mut fields = Vec::new();
+					for (i, fty) in typing.variant_tys(variant, substs).into_iter().enumerate() {
+						fields.push(match typing.field_offset(ty, Some(idx), i) {
+							Some(bytes) => self.load_value(offset(addr.clone(), bytes), fty, nid, typing, sir),
+							None => Value::Opaque
+						});
+					}
+					variants.push(Value::Aggregate(fields));
+				}
+				Value::Enum(discr, variants)
+			},
+			_ => match typing.field_tys(ty) {
+				Some(tys) => {
+					let mut fields = Vec::new();
+					for (i, fty) in tys.into_iter().enumerate() {
+						fields.push(match typing.field_offset(ty, None, i) {
+							Some(bytes) => self.load_value(offset(addr.clone(), bytes), fty, nid, typing, sir),
+							None => Value::Opaque
+						});
+					}
+					Value::Aggregate(fields)
+				},
+				None => Value::Opaque
+			}
+		}
+	}
+
+	/*
+	 * Stores val as a value of type ty at addr. Variants that val does not describe keep their old
+	 * contents; any other missing part is overwritten with a fresh value. Fields whose offset is not
+	 * known are not stored, and load_value reads them as opaque.
+	 */
+	pub fn store_value<'a,'tcx>(&mut self, addr: Expr, ty: Ty<'tcx>, val: Value, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) {
+		match (val, typing.is_pointer(ty), typing.sort_of(ty), typing.field_tys(ty), &ty.sty) {
+			(Value::Uninit, _, _, _, _) => if let Some(size) = typing.size_of(ty) {
+				self.deinit(addr, size)
+			},
+			(val @ Value::Scalar(_), true, _, _, _) | (val @ Value::Pointer(..), true, _, _, _) | (val @ Value::Slice(..), true, _, _, _) => {
+				self.store_pointer(addr, val, typing.is_fat(ty), sir)
+			},
+			(Value::Scalar(expr), false, _, _, &TyKind::Bool) => {
+				self.store(addr, byte_sort(), CellKind::Data, Expr::Ite(Box::new(expr), Box::new(byte(1)), Box::new(byte(0))))
+			},
+			(Value::Scalar(expr), false, Some(sort), _, _) | (Value::Pointer(expr, _), false, Some(sort), _, _) | (Value::Slice(expr, _, _), false, Some(sort), _, _) => {
+				self.store(addr, sort, CellKind::Data, expr)
+			},
+			(Value::Enum(discr, variants), _, None, _, &TyKind::Adt(def, substs)) => {
+				self.store_discriminant(addr.clone(), def, discr, typing);
+				for ((idx, variant), val) in def.variants.iter_enumerated().zip(variants.into_iter()) {
+					if let Value::Aggregate(fields) = val {
+						for (i, (fty, val)) in typing.variant_tys(variant, substs).into_iter().zip(fields.into_iter()).enumerate() {
+							if let Some(bytes) = typing.field_offset(ty, Some(idx), i) {
+								self.store_value(offset(addr.clone(), bytes), fty, val, nid, typing, sir);
+							}
+						}
+					}
+				}
+			},
+			(Value::Aggregate(vals), _, None, Some(tys), _) => {
+				for (i, (fty, val)) in tys.into_iter().zip(vals.into_iter()).enumerate() {
+					if let Some(bytes) = typing.field_offset(ty, None, i) {
+						self.store_value(offset(addr.clone(), bytes), fty, val, nid, typing, sir);
+					}
+				}
+			},
+			_ => match fresh_value(ty, typing, sir) {
+				Value::Opaque => (),
+				val => self.store_value(addr, ty, val, nid, typing, sir)
+			}
+		}
+	}
+}
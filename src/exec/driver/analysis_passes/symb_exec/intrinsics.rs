@@ -0,0 +1,126 @@
+use rustc::hir::def_id::DefId;
+use rustc::ty::{Ty,TyCtxt};
+use rustc::mir::BinOp;
+use rustc_target::spec::abi::Abi;
+use crate::exec::driver::analysis_passes::sir::{Sir,NodeId,Expr,Rator,SymTy,ObligationKind};
+use super::memory::{Memory,Typing,Value,invalid_range,report};
+use super::{binop_expr,offset_expr,overflow_expr,shift_overflow,division_by_zero,division_overflow};
+
+
+pub fn intrinsic_name<'a,'tcx>(did: DefId, tcx: TyCtxt<'a,'tcx,'tcx>) -> Option<String> {
+	if tcx.fn_sig(did).abi() == Abi::RustIntrinsic {
+		Some(tcx.item_name(did).to_string())
+	} else {
+		None
+	}
+}
+
+/*
+ * The result of calling the intrinsic name on args, or None if the intrinsic is not modelled and
+ * should be treated like any other callee without MIR.
+ */
+pub fn evaluate<'a,'tcx>(name: &str, args: Vec<(Value, Ty<'tcx>)>, dest_ty: Ty<'tcx>, memory: &mut Memory<'tcx>, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) -> Option<Value> {
+	let mut args = args.into_iter();
+	match name {
+		"offset" | "arith_offset" => {
+			let (ptr, ptr_ty) = args.next()?;
+			let (count, count_ty) = args.next()?;
+			let prov = ptr.provenance();
+			let addr = offset_expr(ptr.scalar()?, count.scalar()?, ptr_ty, count_ty, typing)?;
+			if name == "offset" {
+				report(prov.out_of_bounds(&addr, 0), ObligationKind::OffsetOutOfBounds, nid, sir);
+			}
+			Some(Value::Pointer(addr, prov))
+		},
+		"unchecked_add" | "unchecked_sub" | "unchecked_mul" => {
+			let (lhs, lhs_ty) = args.next()?;
+			let (rhs, rhs_ty) = args.next()?;
+			let (lhs, rhs) = (lhs.scalar()?, rhs.scalar()?);
+			let op = match name {
+				"unchecked_add" => BinOp::Add,
+				"unchecked_sub" => BinOp::Sub,
+				_ => BinOp::Mul
+			};
+			report(overflow_expr(op, lhs.clone(), rhs.clone(), lhs_ty, rhs_ty), ObligationKind::ArithmeticOverflow, nid, sir);
+			Some(Value::Scalar(binop_expr(op, lhs, rhs, lhs_ty, rhs_ty, typing)))
+		},
+		"add_with_overflow" | "sub_with_overflow" | "mul_with_overflow" => {
+			let (lhs, lhs_ty) = args.next()?;
+			let (rhs, rhs_ty) = args.next()?;
+			let (lhs, rhs) = (lhs.scalar()?, rhs.scalar()?);
+			let op = match name {
+				"add_with_overflow" => BinOp::Add,
+				"sub_with_overflow" => BinOp::Sub,
+				_ => BinOp::Mul
+			};
+			let overflow = overflow_expr(op, lhs.clone(), rhs.clone(), lhs_ty, rhs_ty);
+			Some(Value::Aggregate(vec![Value::Scalar(binop_expr(op, lhs, rhs, lhs_ty, rhs_ty, typing)), Value::Scalar(overflow)]))
+		},
+		"volatile_load" | "unaligned_volatile_load" => {
+			let (ptr, ptr_ty) = args.next()?;
+			Some(memory.load_at(ptr, ptr_ty.builtin_deref(true)?.ty, nid, typing, sir))
+		},
+		"move_val_init" | "volatile_store" | "unaligned_volatile_store" => {
+			let (ptr, ptr_ty) = args.next()?;
+			let (val, _) = args.next()?;
+			memory.store_at(ptr, ptr_ty.builtin_deref(true)?.ty, val, nid, typing, sir);
+			Some(Value::Aggregate(vec![]))
+		},
+		"copy_nonoverlapping" | "copy" => {
+			let (src, src_ty) = args.next()?;
+			let (dst, _) = args.next()?;
+			let (count, _) = args.next()?;
+			let ty = src_ty.builtin_deref(true)?.ty;
+			let len = Expr::BinOp(Rator::Mul, Box::new(count.clone().scalar()?), Box::new(Expr::Value(SymTy::pointer(typing.size_of(ty)? as u128))));
+			let align = typing.align_of(ty)?;
+			let mut violation = Expr::BinOp(Rator::Or, Box::new(invalid_range(&src, len.clone(), align)?), Box::new(invalid_range(&dst, len.clone(), align)?));
+			if name == "copy_nonoverlapping" {
+				let (from, to) = (src.clone().scalar()?, dst.clone().scalar()?);
+				let overlap = Expr::BinOp(Rator::And,
+					Box::new(Expr::BinOp(Rator::LessThan, Box::new(from.clone()), Box::new(Expr::BinOp(Rator::Add, Box::new(to.clone()), Box::new(len.clone()))))),
+					Box::new(Expr::BinOp(Rator::LessThan, Box::new(to), Box::new(Expr::BinOp(Rator::Add, Box::new(from), Box::new(len))))));
+				violation = Expr::BinOp(Rator::Or, Box::new(violation), Box::new(overlap));
+			}
+			report(violation, ObligationKind::PreconditionViolation, nid, sir);
+			match count.scalar()? {
+				Expr::Value(SymTy::Integer(1, _)) => {
+					let val = memory.load_at(src, ty, nid, typing, sir);
+					memory.store_at(dst, ty, val, nid, typing, sir);
+				},
+				_ => memory.havoc_heap()
+			}
+			Some(Value::Aggregate(vec![]))
+		},
+		"unchecked_div" | "unchecked_rem" | "exact_div" => {
+			let (lhs, lhs_ty) = args.next()?;
+			let (rhs, rhs_ty) = args.next()?;
+			let (lhs, rhs) = (lhs.scalar()?, rhs.scalar()?);
+			report(division_by_zero(rhs.clone(), rhs_ty), ObligationKind::DivisionByZero, nid, sir);
+			if let Some(violation) = division_overflow(lhs.clone(), rhs.clone(), lhs_ty) {
+				report(violation, ObligationKind::DivisionOverflow, nid, sir);
+			}
+			if name == "exact_div" {
+				let rem = binop_expr(BinOp::Rem, lhs.clone(), rhs.clone(), lhs_ty, rhs_ty, typing);
+				let inexact = Expr::BinOp(Rator::NotEqual, Box::new(rem), Box::new(Expr::Value(SymTy::from_scalar(0, lhs_ty))));
+				let divides = Expr::UnOp(Rator::Not, Box::new(division_by_zero(rhs.clone(), rhs_ty)));
+				report(Expr::BinOp(Rator::And, Box::new(divides), Box::new(inexact)), ObligationKind::InexactDivision, nid, sir);
+			}
+			let op = if name == "unchecked_rem" { BinOp::Rem } else { BinOp::Div };
+			Some(Value::Scalar(binop_expr(op, lhs, rhs, lhs_ty, rhs_ty, typing)))
+		},
+		"unchecked_shl" | "unchecked_shr" => {
+			let (lhs, lhs_ty) = args.next()?;
+			let (rhs, rhs_ty) = args.next()?;
+			let (lhs, rhs) = (lhs.scalar()?, rhs.scalar()?);
+			report(shift_overflow(rhs.clone(), lhs_ty, rhs_ty), ObligationKind::ShiftOverflow, nid, sir);
+			let op = if name == "unchecked_shl" { BinOp::Shl } else { BinOp::Shr };
+			Some(Value::Scalar(binop_expr(op, lhs, rhs, lhs_ty, rhs_ty, typing)))
+		},
+		"uninit" => Some(Value::Uninit),
+		"transmute" => {
+			let (val, val_ty) = args.next()?;
+			Some(memory.transmute(val, val_ty, dest_ty, nid, typing, sir))
+		},
+		_ => None
+	}
+}
@@ -0,0 +1,1470 @@
+use rustc::mir::{Mir,Place,PlaceBase,PlaceElem,Local,Operand,ProjectionElem,BorrowKind,StaticKind};
+use rustc::hir::Mutability;
+use rustc::mir::interpret::{ConstValue,Scalar};
+use rustc::ty::{Ty,TyKind,TyCtxt,ParamEnv,AdtDef,VariantDef};
+use rustc::ty::subst::SubstsRef;
+use rustc::ty::layout::{VariantIdx,LayoutCx,LayoutOf,TyLayout};
+use rustc::ty::util::IntTypeExt;
+use rustc::hir::def_id::DefId;
+use syntax_pos::DUMMY_SP;
+use std::collections::{HashMap,HashSet};
+use std::ops::Bound;
+use crate::exec::driver::analysis_passes::sir::{Sir,Name,NodeId,Declaration,Expr,Rator,SymTy,BitWidth,MirVariableProp,ObligationKind};
+use crate::exec::driver::analysis_passes::sir::{PathElem,VarLocation,extend_location};
+use super::heap::{Heap,Provenance,Permission,offset};
+
+
+/*
+ * Arrays longer than this are not tracked element by element outside of the heap.
+ */
+pub const MAX_ARRAY_LEN: u64 = 64;
+
+
+#[derive(Clone,Copy)]
+pub struct Typing<'a,'tcx: 'a> {
+	mir: &'a Mir<'tcx>,
+	tcx: TyCtxt<'a,'tcx,'tcx>
+}
+
+impl <'a,'tcx> Typing<'a,'tcx> {
+	pub fn new(mir: &'a Mir<'tcx>, tcx: TyCtxt<'a,'tcx,'tcx>) -> Typing<'a,'tcx> {
+		Typing {
+			mir,
+			tcx
+		}
+	}
+
+	pub fn get_mir(&self) -> &'a Mir<'tcx> {
+		self.mir
+	}
+
+	pub fn tcx(&self) -> TyCtxt<'a,'tcx,'tcx> {
+		self.tcx
+	}
+
+	pub fn local_ty(&self, lcl: Local) -> Ty<'tcx> {
+		self.mir.local_decls[lcl].ty
+	}
+
+	pub fn place_ty(&self, plc: &Place<'tcx>) -> Ty<'tcx> {
+		plc.ty(self.mir, self.tcx).to_ty(self.tcx)
+	}
+
+	pub fn operand_ty(&self, rand: &Operand<'tcx>) -> Ty<'tcx> {
+		rand.ty(self.mir, self.tcx)
+	}
+
+	pub fn normalize(&self, ty: Ty<'tcx>) -> Ty<'tcx> {
+		self.tcx.normalize_erasing_regions(ParamEnv::reveal_all(), ty)
+	}
+
+	fn layout_cx(&self) -> LayoutCx<'tcx, TyCtxt<'a,'tcx,'tcx>> {
+		LayoutCx {
+			tcx: self.tcx,
+			param_env: ParamEnv::reveal_all()
+		}
+	}
+
+	/*
+	 * The layout of ty, or None if it is not known, as for a type that depends on a type parameter.
+	 */
+	fn layout_of(&self, ty: Ty<'tcx>) -> Option<TyLayout<'tcx>> {
+		self.layout_cx().layout_of(ty).ok()
+	}
+
+	pub fn size_of(&self, ty: Ty<'tcx>) -> Option<u64> {
+		self.layout_of(ty).map(|layout| layout.size.bytes())
+	}
+
+	pub fn align_of(&self, ty: Ty<'tcx>) -> Option<u64> {
+		self.layout_of(ty).map(|layout| layout.align.abi.bytes())
+	}
+
+	/*
+	 * Byte offset of a field of ty, or of a field of one of its variants if ty is an enum.
+	 */
+	pub fn field_offset(&self, ty: Ty<'tcx>, variant: Option<VariantIdx>, field: usize) -> Option<u64> {
+		let layout = match variant {
+			Some(idx) => self.layout_of(ty)?.for_variant(&self.layout_cx(), idx),
+			None => self.layout_of(ty)?
+		};
+		Some(layout.fields.offset(field).bytes())
+	}
+
+	/*
+	 * The sort of the single name a value of ty is encoded as, or None if it is not a scalar.
+	 */
+	pub fn sort_of(&self, ty: Ty<'tcx>) -> Option<SymTy> {
+		if ty.is_bool() || BitWidth::of(ty).is_some() {
+			Some(Declaration::decl_from(ty, None).get_type().clone())
+		} else {
+			None
+		}
+	}
+
+	/*
+	 * Whether a value of type ty cannot carry a pointer.
+	 */
+	pub fn is_plain(&self, ty: Ty<'tcx>) -> bool {
+		ty.is_bool() || ty.is_char() || ty.is_integral() || ty.is_floating_point() || ty.is_unit()
+	}
+
+	/*
+	 * Whether a value of type ty is an address that carries the provenance of its allocation.
+	 */
+	pub fn is_pointer(&self, ty: Ty<'tcx>) -> bool {
+		match ty.sty {
+			TyKind::RawPtr(_) | TyKind::Ref(..) => true,
+			TyKind::Adt(def, _) => def.is_box(),
+			_ => false
+		}
+	}
+
+	pub fn is_non_null(&self, ty: Ty<'tcx>) -> bool {
+		match ty.sty {
+			TyKind::Ref(..) | TyKind::FnPtr(_) => true,
+			TyKind::Adt(def, _) => def.is_box(),
+			_ => false
+		}
+	}
+
+	/*
+	 * Whether ty wraps a single scalar that may not be zero, as NonNull and the NonZero integers do.
+	 */
+	pub fn is_non_zero(&self, ty: Ty<'tcx>) -> bool {
+		match ty.sty {
+			TyKind::Adt(def, _) if def.is_struct() => match self.tcx.layout_scalar_valid_range(def.did) {
+				(Bound::Included(start), _) => start > 0,
+				_ => false
+			},
+			_ => false
+		}
+	}
+
+	/*
+	 * Whether no value of type ty exists, including structs and tuples with an uninhabited field
+	 * and enums all of whose variants have one.
+	 */
+	pub fn is_uninhabited(&self, ty: Ty<'tcx>) -> bool {
+		ty.conservative_is_privately_uninhabited(self.tcx)
+	}
+
+	/*
+	 * Whether ty is a union, whose bytes may be left uninitialized.
+	 */
+	pub fn is_union(&self, ty: Ty<'tcx>) -> bool {
+		match ty.sty {
+			TyKind::Adt(def, _) => def.is_union(),
+			_ => false
+		}
+	}
+
+	/*
+	 * The condition a scalar of type ty must satisfy besides fitting its sort, if there is one.
+	 */
+	pub fn validity(&self, ty: Ty<'tcx>) -> Option<fn(Expr) -> Expr> {
+		if ty.is_char() {
+			Some(valid_char)
+		} else if self.is_non_null(ty) {
+			Some(non_null)
+		} else {
+			None
+		}
+	}
+
+	/*
+	 * The number of bytes accessed through a place of type ty, or None if ty is unsized or its
+	 * layout is not known.
+	 */
+	pub fn access_size(&self, ty: Ty<'tcx>) -> Option<u64> {
+		if ty.is_sized(self.tcx.at(DUMMY_SP), ParamEnv::reveal_all()) {
+			self.size_of(ty)
+		} else {
+			None
+		}
+	}
+
+	pub fn pointee_size(&self, ptr_ty: Ty<'tcx>) -> Option<u64> {
+		self.size_of(ptr_ty.builtin_deref(true)?.ty)
+	}
+
+	pub fn pointee_align(&self, ptr_ty: Ty<'tcx>) -> Option<u64> {
+		self.align_of(ptr_ty.builtin_deref(true)?.ty)
+	}
+
+	pub fn is_freeze(&self, ty: Ty<'tcx>) -> bool {
+		ty.is_freeze(self.tcx, ParamEnv::reveal_all(), DUMMY_SP)
+	}
+
+	/*
+	 * Whether the static did, of type ty, may never be written: it is not mut and has no interior
+	 * mutability.
+	 */
+	pub fn is_read_only_static(&self, did: DefId, ty: Ty<'tcx>) -> bool {
+		self.tcx.is_static(did) != Some(Mutability::MutMutable) && self.is_freeze(ty)
+	}
+
+	/*
+	 * The index of the field called name in the struct ty.
+	 */
+	pub fn field_named(&self, ty: Ty<'tcx>, name: &str) -> Option<usize> {
+		match ty.sty {
+			TyKind::Adt(def, _) if def.is_struct() => def.non_enum_variant().fields.iter().position(|field| field.ident.as_str() == name),
+			_ => None
+		}
+	}
+
+	pub fn needs_drop(&self, ty: Ty<'tcx>) -> bool {
+		ty.needs_drop(self.tcx, ParamEnv::reveal_all())
+	}
+
+	pub fn has_dtor(&self, def: &'tcx AdtDef) -> bool {
+		def.has_dtor(self.tcx)
+	}
+
+	pub fn array_len(&self, ty: Ty<'tcx>) -> Option<u64> {
+		match ty.sty {
+			TyKind::Array(_, len) => Some(len.unwrap_usize(self.tcx)),
+			_ => None
+		}
+	}
+
+	pub fn elem_ty(&self, ty: Ty<'tcx>) -> Option<Ty<'tcx>> {
+		match ty.sty {
+			TyKind::Array(elem, _) => Some(elem),
+			_ => self.slice_elem(ty)
+		}
+	}
+
+	/*
+	 * The element type of ty if it is a slice or str.
+	 */
+	pub fn slice_elem(&self, ty: Ty<'tcx>) -> Option<Ty<'tcx>> {
+		match ty.sty {
+			TyKind::Slice(elem) => Some(elem),
+			TyKind::Str => Some(self.tcx.types.u8),
+			_ => None
+		}
+	}
+
+	/*
+	 * Whether ty is a pointer to a slice, which carries the length of the slice next to its address.
+	 */
+	pub fn is_fat(&self, ty: Ty<'tcx>) -> bool {
+		self.is_pointer(ty) && ty.builtin_deref(true).and_then(|tm| self.slice_elem(tm.ty)).is_some()
+	}
+
+	pub fn usize_sort(&self) -> SymTy {
+		self.sort_of(self.tcx.types.usize).unwrap()
+	}
+
+	pub fn field_tys(&self, ty: Ty<'tcx>) -> Option<Vec<Ty<'tcx>>> {
+		match ty.sty {
+			TyKind::Tuple(tys) => Some(tys.iter().cloned().collect()),
+			TyKind::Closure(did, substs) => Some(substs.upvar_tys(did, self.tcx).collect()),
+			TyKind::Array(elem, _) => match self.array_len(ty) {
+				Some(len) if len <= MAX_ARRAY_LEN => Some(vec![elem; len as usize]),
+				_ => None
+			},
+			TyKind::Adt(def, substs) if def.is_struct() => Some(self.variant_tys(def.non_enum_variant(), substs)),
+			_ => None
+		}
+	}
+
+	pub fn variant_tys(&self, variant: &VariantDef, substs: SubstsRef<'tcx>) -> Vec<Ty<'tcx>> {
+		variant.fields.iter().map(|field| self.normalize(field.ty(self.tcx, substs))).collect()
+	}
+
+	pub fn discr_ty(&self, def: &'tcx AdtDef) -> Ty<'tcx> {
+		def.repr.discr_type().to_ty(self.tcx)
+	}
+
+	pub fn discriminant(&self, def: &'tcx AdtDef, variant: VariantIdx) -> SymTy {
+		SymTy::from_scalar(def.discriminant_for_variant(self.tcx, variant).val, self.discr_ty(def))
+	}
+
+	pub fn discriminants(&self, def: &'tcx AdtDef) -> Vec<SymTy> {
+		let discr_ty = self.discr_ty(def);
+		def.discriminants(self.tcx).map(|(_, discr)| SymTy::from_scalar(discr.val, discr_ty)).collect()
+	}
+}
+
+/*
+ * A value produced by evaluating an operand or rvalue. Aggregates are kept field by field so
+ * that they can be written into a Slot without flattening. Uninit is only ever read from unions,
+ * such as MaybeUninit, which may hold it.
+ */
+#[derive(Clone,Debug)]
+pub enum Value {
+	Scalar(Expr),
+	Pointer(Expr, Provenance),
+	Slice(Expr, Expr, Provenance),
+	Aggregate(Vec<Value>),
+	Enum(Expr, Vec<Value>),
+	Uninit,
+	Opaque
+}
+
+impl Value {
+	/*
+	 * The scalar or address of this value, or None if it has none, as for a float or a value that is
+	 * not modelled.
+	 */
+	pub fn scalar(self) -> Option<Expr> {
+		match self {
+			Value::Scalar(expr) | Value::Pointer(expr, _) | Value::Slice(expr, _, _) => Some(expr),
+			_ => None
+		}
+	}
+
+	pub fn provenance(&self) -> Provenance {
+		match self {
+			Value::Pointer(_, prov) | Value::Slice(_, _, prov) => prov.clone(),
+			_ => Provenance::unknown()
+		}
+	}
+
+	pub fn length(&self) -> Option<Expr> {
+		match self {
+			Value::Slice(_, len, _) => Some(len.clone()),
+			_ => None
+		}
+	}
+}
+
+/*
+ * An enum keeps the fields of every variant alongside its discriminant, the discriminant being
+ * restricted to the values in discriminants whenever it is unconstrained. A pointer keeps its
+ * provenance next to its address, and a pointer to a slice also keeps the length of the slice.
+ */
+#[derive(Clone,Debug)]
+enum Slot {
+	Scalar(Name),
+	Pointer{addr: Name, base: Name, size: Name, offset: Name, limit: Name, tag: Name, len: Option<Name>},
+	Aggregate(Vec<Slot>),
+	Enum{discr: Name, discriminants: Vec<SymTy>, variants: Vec<Slot>},
+	Opaque
+}
+
+pub fn one_of(expr: Expr, discriminants: &Vec<SymTy>) -> Expr {
+	discriminants.iter().map(|discr| Expr::BinOp(Rator::Eq, Box::new(expr.clone()), Box::new(Expr::Value(discr.clone()))))
+		.fold(Expr::Value(SymTy::from_boolean(false)), |x, y| Expr::BinOp(Rator::Or, Box::new(x), Box::new(y)))
+}
+
+pub fn non_null(expr: Expr) -> Expr {
+	Expr::BinOp(Rator::NotEqual, Box::new(expr), Box::new(Expr::Value(SymTy::pointer(0))))
+}
+
+pub fn non_zero(expr: Expr, sort: &SymTy) -> Expr {
+	Expr::BinOp(Rator::NotEqual, Box::new(expr), Box::new(Expr::Value(SymTy::pointer(0).with_sort(sort))))
+}
+
+/*
+ * Holds when expr, a char, is a unicode scalar value.
+ */
+pub fn valid_char(expr: Expr) -> Expr {
+	let code_point = |val: u128| Box::new(Expr::Value(SymTy::Integer(val, BitWidth::new(32, false))));
+	let low = Expr::BinOp(Rator::LessThan, Box::new(expr.clone()), code_point(0xD800));
+	let high = Expr::BinOp(Rator::And,
+		Box::new(Expr::BinOp(Rator::LessEqual, code_point(0xE000), Box::new(expr.clone()))),
+		Box::new(Expr::BinOp(Rator::LessEqual, Box::new(expr), code_point(0x10FFFF))));
+	Expr::BinOp(Rator::Or, Box::new(low), Box::new(high))
+}
+
+/*
+ * Names violation, the condition under which the operation at nid is undefined or panics, and
+ * attaches an obligation of kind to it.
+ */
+pub fn report(violation: Expr, kind: ObligationKind, nid: NodeId, sir: &mut Sir) {
+	let nm = name_of(violation, SymTy::from_boolean(false), nid, sir);
+	sir.add_property_to_declaration(nm, MirVariableProp::Obligation{kind, node: nid, violation: Expr::Ref(nm)});
+}
+
+pub fn aligned(expr: Expr, align: u64) -> Expr {
+	let misalignment = Expr::BinOp(Rator::BitAnd, Box::new(expr), Box::new(Expr::Value(SymTy::pointer(align as u128 - 1))));
+	Expr::BinOp(Rator::Eq, Box::new(misalignment), Box::new(Expr::Value(SymTy::pointer(0))))
+}
+
+/*
+ * Holds when ptr is not valid for len bytes as the standard library requires of raw pointers: it
+ * is null or not aligned to align, or the bytes leave its allocation. This holds even when len is
+ * zero.
+ */
+pub fn invalid_range(ptr: &Value, len: Expr, align: u64) -> Option<Expr> {
+	let addr = ptr.clone().scalar()?;
+	let misaligned = Expr::UnOp(Rator::Not, Box::new(aligned(addr.clone(), align)));
+	let null = Expr::UnOp(Rator::Not, Box::new(non_null(addr.clone())));
+	let exceeds = ptr.provenance().exceeds(&addr, len);
+	Some(Expr::BinOp(Rator::Or, Box::new(Expr::BinOp(Rator::Or, Box::new(null), Box::new(misaligned))), Box::new(exceeds)))
+}
+
+fn assign_scalar(nm: &mut Name, expr: Expr, nid: NodeId, sir: &mut Sir) {
+	let new_name = sir.add_declaration(sir.get_declaration(*nm).without_location());
+	sir.add_expr_to_node(nid, Expr::BinOp(Rator::Eq, Box::new(Expr::Ref(new_name)), Box::new(expr)));
+	*nm = new_name;
+}
+
+impl Slot {
+	fn fresh<'a,'tcx>(ty: Ty<'tcx>, typing: Typing<'a,'tcx>, loc: Option<VarLocation>, sir: &mut Sir) -> Slot {
+		if typing.is_pointer(ty) {
+			let addr = sir.add_declaration(Declaration::decl_from(ty, loc));
+			if typing.is_non_null(ty) {
+				sir.add_axiom(non_null(Expr::Ref(addr)));
+			}
+			let base = sir.add_declaration(Declaration::of_sort(SymTy::pointer(0)));
+			let size = sir.add_declaration(Declaration::of_sort(SymTy::pointer(0)));
+			let offset = sir.add_declaration(Declaration::of_sort(SymTy::pointer(0)));
+			let limit = sir.add_declaration(Declaration::of_sort(SymTy::pointer(0)));
+			let tag = sir.add_declaration(Declaration::of_sort(SymTy::pointer(0)));
+			let len = if typing.is_fat(ty) {
+				Some(sir.add_declaration(Declaration::of_sort_at(typing.usize_sort(), extend_location(&loc, PathElem::Length))))
+			} else {
+				None
+			};
+			return Slot::Pointer{addr, base, size, offset, limit, tag, len};
+		}
+
+		if ty.is_bool() || BitWidth::of(ty).is_some() {
+			let nm = sir.add_declaration(Declaration::decl_from(ty, loc));
+			if let Some(valid) = typing.validity(ty) {
+				sir.add_axiom(valid(Expr::Ref(nm)));
+			}
+			return Slot::Scalar(nm);
+		}
+
+		if let TyKind::Adt(def, substs) = ty.sty {
+			if def.is_enum() {
+				let discr = sir.add_declaration(Declaration::decl_from(typing.discr_ty(def), extend_location(&loc, PathElem::Discriminant)));
+				let discriminants = typing.discriminants(def);
+				sir.add_axiom(one_of(Expr::Ref(discr), &discriminants));
+				let variants = def.variants.iter().enumerate().map(|(i, variant)| {
+					let variant_loc = extend_location(&loc, PathElem::Variant(i));
+					Slot::Aggregate(typing.variant_tys(variant, substs).into_iter().enumerate()
+						.map(|(j, fty)| Slot::fresh(fty, typing, extend_location(&variant_loc, PathElem::Field(j)), sir)).collect())
+				}).collect();
+				return Slot::Enum{discr, discriminants, variants};
+			}
+		}
+
+		match typing.field_tys(ty) {
+			Some(fields) => Slot::Aggregate(fields.into_iter().enumerate()
+				.map(|(i, fty)| Slot::fresh(fty, typing, extend_location(&loc, PathElem::Field(i)), sir)).collect()),
+			None => Slot::Opaque
+		}
+	}
+
+	/*
+	 * A fresh slot whose pointers point into allocations that are not known, at addresses aligned
+	 * for their pointees where the layout of the pointee is known.
+	 */
+	fn unknown<'a,'tcx>(ty: Ty<'tcx>, typing: Typing<'a,'tcx>, loc: Option<VarLocation>, sir: &mut Sir) -> Slot {
+		let slot = Slot::fresh(ty, typing, loc, sir);
+		let mut unknown = Vec::new();
+		slot.unknown_provenance(ty, typing, &mut unknown);
+		for expr in unknown {
+			sir.add_axiom(expr);
+		}
+		slot
+	}
+
+	fn unknown_provenance<'a,'tcx>(&self, ty: Ty<'tcx>, typing: Typing<'a,'tcx>, total: &mut Vec<Expr>) {
+		match (self, typing.field_tys(ty), &ty.sty) {
+			(Slot::Pointer{addr, base, offset, tag, ..}, _, _) => {
+				total.push(Expr::BinOp(Rator::Eq, Box::new(Expr::Ref(*base)), Box::new(Expr::Value(SymTy::pointer(0)))));
+				total.push(Expr::BinOp(Rator::Eq, Box::new(Expr::Ref(*offset)), Box::new(Expr::Value(SymTy::pointer(0)))));
+				total.push(Expr::BinOp(Rator::Eq, Box::new(Expr::Ref(*tag)), Box::new(Expr::Value(SymTy::pointer(0)))));
+				if let Some(align) = typing.pointee_align(ty) {
+					total.push(aligned(Expr::Ref(*addr), align));
+				}
+			},
+			(Slot::Enum{variants, ..}, _, &TyKind::Adt(def, substs)) => for (variant, slot) in def.variants.iter().zip(variants.iter()) {
+				if let Slot::Aggregate(fields) = slot {
+					for (fty, field) in typing.variant_tys(variant, substs).into_iter().zip(fields.iter()) {
+						field.unknown_provenance(fty, typing, total);
+					}
+				}
+			},
+			(Slot::Aggregate(fields), Some(tys), _) => for (fty, field) in tys.into_iter().zip(fields.iter()) {
+				field.unknown_provenance(fty, typing, total);
+			},
+			_ => ()
+		}
+	}
+
+	/*
+	 * The slot of a field. Projecting into a slot whose structure is not modelled stays in that slot,
+	 * so that the field reads as it does and writes to it are dropped.
+	 */
+	fn field_mut(&mut self, field: usize) -> &mut Slot {
+		match self {
+			Slot::Aggregate(fields) if field < fields.len() => &mut fields[field],
+			slot => slot
+		}
+	}
+
+	fn variant_mut(&mut self, variant: usize) -> &mut Slot {
+		match self {
+			Slot::Enum{variants, ..} if variant < variants.len() => &mut variants[variant],
+			slot => slot
+		}
+	}
+
+	fn value(&self) -> Value {
+		match self {
+			Slot::Scalar(nm) => Value::Scalar(Expr::Ref(*nm)),
+			Slot::Pointer{addr, base, size, offset, limit, tag, len} => {
+				let prov = Provenance::new(Expr::Ref(*base), Expr::Ref(*size)).within(Expr::Ref(*offset), Expr::Ref(*limit)).with_tag(Expr::Ref(*tag));
+				match len {
+					Some(len) => Value::Slice(Expr::Ref(*addr), Expr::Ref(*len), prov),
+					None => Value::Pointer(Expr::Ref(*addr), prov)
+				}
+			},
+			Slot::Aggregate(fields) => Value::Aggregate(fields.iter().map(|field| field.value()).collect()),
+			Slot::Enum{discr, variants, ..} => Value::Enum(Expr::Ref(*discr), variants.iter().map(|variant| variant.value()).collect()),
+			Slot::Opaque => Value::Opaque
+		}
+	}
+
+	fn assign(&mut self, val: Value, nid: NodeId, sir: &mut Sir) {
+		match val {
+			Value::Scalar(_) | Value::Pointer(..) | Value::Slice(..) => match (self, val.clone().scalar()) {
+				(Slot::Scalar(nm), Some(expr)) => assign_scalar(nm, expr, nid, sir),
+				(Slot::Pointer{addr, base, size, offset, limit, tag, len}, Some(expr)) => {
+					let (prov, length) = (val.provenance(), val.length());
+					assign_scalar(addr, expr, nid, sir);
+					assign_scalar(base, prov.base(), nid, sir);
+					assign_scalar(size, prov.size(), nid, sir);
+					assign_scalar(offset, prov.offset(), nid, sir);
+					assign_scalar(limit, prov.limit(), nid, sir);
+					assign_scalar(tag, prov.tag(), nid, sir);
+					match (len.as_mut(), length) {
+						(Some(len), Some(length)) => assign_scalar(len, length, nid, sir),
+						(Some(len), None) => *len = sir.add_declaration(sir.get_declaration(*len).without_location()),
+						(None, _) => ()
+					}
+				},
+				(slot, _) => slot.havoc(sir)
+			},
+			Value::Aggregate(vals) => match self {
+				Slot::Aggregate(fields) => for (field, val) in fields.iter_mut().zip(vals.into_iter()) {
+					field.assign(val, nid, sir);
+				},
+				slot => slot.havoc(sir)
+			},
+			Value::Enum(expr, vals) => match self {
+				Slot::Enum{discr, variants, ..} => {
+					assign_scalar(discr, expr, nid, sir);
+					for (variant, val) in variants.iter_mut().zip(vals.into_iter()) {
+						variant.assign(val, nid, sir);
+					}
+				},
+				slot => slot.havoc(sir)
+			},
+			Value::Uninit | Value::Opaque => self.havoc(sir)
+		}
+	}
+
+	fn havoc(&mut self, sir: &mut Sir) {
+		match self {
+			Slot::Scalar(nm) => *nm = sir.add_declaration(sir.get_declaration(*nm).without_location()),
+			Slot::Pointer{addr, base, size, offset, limit, tag, len} => {
+				*addr = sir.add_declaration(sir.get_declaration(*addr).without_location());
+				if let Some(len) = len {
+					*len = sir.add_declaration(sir.get_declaration(*len).without_location());
+				}
+				*base = sir.add_declaration(Declaration::of_sort(SymTy::pointer(0)));
+				*size = sir.add_declaration(Declaration::of_sort(SymTy::pointer(0)));
+				*offset = sir.add_declaration(Declaration::of_sort(SymTy::pointer(0)));
+				*limit = sir.add_declaration(Declaration::of_sort(SymTy::pointer(0)));
+				*tag = sir.add_declaration(Declaration::of_sort(SymTy::pointer(0)));
+				sir.add_axiom(Expr::BinOp(Rator::Eq, Box::new(Expr::Ref(*base)), Box::new(Expr::Value(SymTy::pointer(0)))));
+				sir.add_axiom(Expr::BinOp(Rator::Eq, Box::new(Expr::Ref(*offset)), Box::new(Expr::Value(SymTy::pointer(0)))));
+				sir.add_axiom(Expr::BinOp(Rator::Eq, Box::new(Expr::Ref(*tag)), Box::new(Expr::Value(SymTy::pointer(0)))));
+			},
+			Slot::Aggregate(fields) => for field in fields.iter_mut() {
+				field.havoc(sir);
+			},
+			Slot::Enum{discr, discriminants, variants} => {
+				*discr = sir.add_declaration(sir.get_declaration(*discr).without_location());
+				sir.add_axiom(one_of(Expr::Ref(*discr), discriminants));
+				for variant in variants.iter_mut() {
+					variant.havoc(sir);
+				}
+			},
+			Slot::Opaque => ()
+		}
+	}
+
+	fn leaves(&self, total: &mut Vec<Name>) {
+		match self {
+			Slot::Scalar(nm) => total.push(*nm),
+			Slot::Pointer{addr, base, size, offset, limit, tag, len} => {
+				total.extend(vec![*addr, *base, *size, *offset, *limit, *tag]);
+				total.extend(len.iter().cloned());
+			},
+			Slot::Aggregate(fields) => for field in fields.iter() {
+				field.leaves(total);
+			},
+			Slot::Enum{discr, variants, ..} => {
+				total.push(*discr);
+				for variant in variants.iter() {
+					variant.leaves(total);
+				}
+			},
+			Slot::Opaque => ()
+		}
+	}
+}
+
+pub fn local_place<'tcx>(lcl: Local) -> Place<'tcx> {
+	Place::Base(PlaceBase::Local(lcl))
+}
+
+fn base_local<'tcx>(plc: &Place<'tcx>) -> Option<Local> {
+	match plc {
+		Place::Base(PlaceBase::Local(lcl)) => Some(*lcl),
+		Place::Base(_) => None,
+		Place::Projection(proj) => base_local(&proj.base)
+	}
+}
+
+/*
+ * A fresh value of type ty that is not tied to any argument.
+ */
+pub fn fresh_value<'a,'tcx>(ty: Ty<'tcx>, typing: Typing<'a,'tcx>, sir: &mut Sir) -> Value {
+	Slot::unknown(ty, typing, None, sir).value()
+}
+
+/*
+ * A fresh scalar of the sort of ty, standing for a value that is not modelled. A type without a
+ * sort, such as a float, gets one of the sort of usize.
+ */
+pub fn fresh_scalar<'a,'tcx>(ty: Ty<'tcx>, typing: Typing<'a,'tcx>, sir: &mut Sir) -> Expr {
+	let sort = typing.sort_of(ty).unwrap_or_else(|| typing.usize_sort());
+	Expr::Ref(sir.add_declaration(Declaration::of_sort(sort)))
+}
+
+/*
+ * Converts a pointer between pointer types. Unsizing a pointer to an array gives the slice its
+ * length, and the address taken out of a slice may only be used within the slice, though it still
+ * points into the allocation of the slice.
+ */
+pub fn cast_pointer<'a,'tcx>(val: Value, from_ty: Ty<'tcx>, to_ty: Ty<'tcx>, typing: Typing<'a,'tcx>) -> Value {
+	match (val, typing.is_fat(from_ty), typing.is_fat(to_ty)) {
+		(Value::Slice(addr, len, prov), true, false) => {
+			let elem_ty = typing.slice_elem(from_ty.builtin_deref(true).unwrap().ty).unwrap();
+			match typing.size_of(elem_ty) {
+				Some(elem_size) => {
+					let size = Expr::BinOp(Rator::Mul, Box::new(len), Box::new(Expr::Value(SymTy::pointer(elem_size as u128))));
+					Value::Pointer(addr.clone(), prov.within_range(addr, size))
+				},
+				None => Value::Pointer(addr, prov)
+			}
+		},
+		(val, false, true) => match (typing.array_len(from_ty.builtin_deref(true).unwrap().ty), val.clone().scalar()) {
+			(Some(len), Some(addr)) => Value::Slice(addr, Expr::Value(SymTy::pointer(len as u128)), val.provenance()),
+			_ => val
+		},
+		(val, _, _) => val
+	}
+}
+
+/*
+ * Where a place lives: in a slot of a local that never had its address taken, or in the heap at
+ * an address. The variant is set when the heap place has been downcast, and len when it is an
+ * array or slice whose length is known.
+ */
+enum Target<'tcx> {
+	Slot(Place<'tcx>),
+	Heap{addr: Expr, prov: Provenance, ty: Ty<'tcx>, variant: Option<VariantIdx>, len: Option<Expr>}
+}
+
+impl<'tcx> Target<'tcx> {
+	/*
+	 * A place of type ty that cannot be resolved, at any address in an allocation that is not known.
+	 */
+	fn unknown(ty: Ty<'tcx>, sir: &mut Sir) -> Target<'tcx> {
+		let addr = Expr::Ref(sir.add_declaration(Declaration::of_sort(SymTy::pointer(0))));
+		Target::Heap{addr, prov: Provenance::unknown(), ty, variant: None, len: None}
+	}
+}
+
+pub fn name_of(expr: Expr, sort: SymTy, nid: NodeId, sir: &mut Sir) -> Name {
+	match expr {
+		Expr::Ref(nm) => nm,
+		_ => {
+			let nm = sir.add_declaration(Declaration::of_sort(sort));
+			sir.add_expr_to_node(nid, Expr::BinOp(Rator::Eq, Box::new(Expr::Ref(nm)), Box::new(expr)));
+			nm
+		}
+	}
+}
+
+/*
+ * Collects the provenance of every pointer in val, a value of type ty, together with the condition
+ * under which val holds it.
+ */
+fn pointers_in<'a,'tcx>(val: Value, ty: Ty<'tcx>, cond: Expr, typing: Typing<'a,'tcx>, total: &mut Vec<(Expr,Provenance)>) {
+	match (val, typing.field_tys(ty), &ty.sty) {
+		(Value::Pointer(_, prov), _, _) | (Value::Slice(_, _, prov), _, _) => total.push((cond, prov)),
+		(Value::Enum(discr, variants), _, &TyKind::Adt(def, substs)) => {
+			for ((idx, variant), val) in def.variants.iter_enumerated().zip(variants.into_iter()) {
+				let active = Expr::BinOp(Rator::Eq, Box::new(discr.clone()), Box::new(Expr::Value(typing.discriminant(def, idx))));
+				let cond = Expr::BinOp(Rator::And, Box::new(cond.clone()), Box::new(active));
+				if let Value::Aggregate(fields) = val {
+					for (fty, field) in typing.variant_tys(variant, substs).into_iter().zip(fields.into_iter()) {
+						pointers_in(field, fty, cond.clone(), typing, total);
+					}
+				}
+			}
+		},
+		(Value::Aggregate(fields), Some(tys), _) => for (fty, field) in tys.into_iter().zip(fields.into_iter()) {
+			pointers_in(field, fty, cond.clone(), typing, total);
+		},
+		_ => ()
+	}
+}
+
+/*
+ * Locals are kept in slots until their address is taken, at which point they are moved to a
+ * fresh allocation in the heap. The heap is shared with the frames this memory calls into.
+ */
+#[derive(Clone)]
+pub struct Memory<'tcx> {
+	assignments: HashMap<Place<'tcx>,Slot>,
+	addresses: HashMap<Local,(Expr,Provenance)>,
+	uninit: HashSet<Local>,
+	stack: Vec<Expr>,
+	heap: Heap
+}
+
+impl <'tcx> Memory <'tcx> {
+	pub fn new() -> Memory<'tcx> {
+		Memory {
+			assignments: HashMap::new(),
+			addresses: HashMap::new(),
+			uninit: HashSet::new(),
+			stack: Vec::new(),
+			heap: Heap::new()
+		}
+	}
+
+	pub fn sharing_heap(other: &Memory<'tcx>) -> Memory<'tcx> {
+		Memory {
+			assignments: HashMap::new(),
+			addresses: HashMap::new(),
+			uninit: HashSet::new(),
+			stack: Vec::new(),
+			heap: other.heap.clone()
+		}
+	}
+
+	pub fn take_heap(&mut self, other: &Memory<'tcx>) {
+		self.heap = other.heap.clone();
+	}
+
+	pub fn havoc_heap(&mut self) {
+		self.heap.havoc();
+	}
+
+	pub fn heap_accessed(&self) -> bool {
+		self.heap.is_accessed()
+	}
+
+	pub fn process_operand<'a>(&mut self, rand: Operand<'tcx>, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) -> Value {
+		match rand {
+			Operand::Copy(plc) | Operand::Move(plc) => self.read(&plc,nid,typing,sir),
+			Operand::Constant(cst) => match cst.literal.val {
+				ConstValue::Scalar(Scalar::Bits{bits, size:_}) if cst.ty.is_bool() || BitWidth::of(cst.ty).is_some() => Value::Scalar(Expr::Value(SymTy::from_scalar(bits, cst.ty))),
+				ConstValue::Slice(_, len) if cst.ty.is_region_ptr() => self.constant_pointer(cst.ty, Some(len), nid, typing, sir),
+				_ if cst.ty.is_region_ptr() && !typing.is_fat(cst.ty) => self.constant_pointer(cst.ty, None, nid, typing, sir),
+				_ if cst.ty.is_unit() => Value::Aggregate(vec![]),
+				_ => Value::Opaque
+			}
+		}
+	}
+
+	/*
+	 * A reference constant, such as a string literal, points into fresh read-only memory. The length
+	 * of a slice constant is known. A constant whose layout is not known points into an allocation
+	 * that is not known either.
+	 */
+	fn constant_pointer<'a>(&mut self, ty: Ty<'tcx>, len: Option<u64>, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) -> Value {
+		let pointee = ty.builtin_deref(true).unwrap().ty;
+		let elem = typing.slice_elem(pointee).unwrap_or(pointee);
+		let (base, prov) = match (typing.size_of(elem), typing.align_of(elem)) {
+			(Some(elem_size), Some(align)) => {
+				let size = elem_size * len.unwrap_or(1);
+				let base = self.heap.allocate(size, align, nid, sir);
+				self.heap.protect(base.clone());
+				(base.clone(), Provenance::new(base, Expr::Value(SymTy::pointer(size as u128))))
+			},
+			_ => (self.fresh_pointer(nid, sir), Provenance::unknown())
+		};
+		match len {
+			Some(len) => Value::Slice(base, Expr::Value(SymTy::pointer(len as u128)), prov),
+			None => Value::Pointer(base, prov)
+		}
+	}
+
+	/*
+	 * The address of a static or a promoted constant. Each static is allocated once and is read-only
+	 * unless is_read_only_static says otherwise, while a promoted constant gets fresh read-only
+	 * memory.
+	 */
+	fn static_address<'a>(&mut self, kind: &StaticKind, ty: Ty<'tcx>, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) -> (Expr, Provenance) {
+		let (size, align) = match (typing.size_of(ty), typing.align_of(ty)) {
+			(Some(size), Some(align)) => (size, align),
+			_ => return (self.fresh_pointer(nid, sir), Provenance::unknown())
+		};
+		let base = match kind {
+			StaticKind::Static(did) => {
+				let read_only = typing.is_read_only_static(*did, ty);
+				self.heap.static_base(*did, |heap| {
+					let base = heap.allocate(size, align, nid, sir);
+					if read_only {
+						heap.protect(base.clone());
+					}
+					base
+				})
+			},
+			StaticKind::Promoted(_) => {
+				let base = self.heap.allocate(size, align, nid, sir);
+				self.heap.protect(base.clone());
+				base
+			}
+		};
+		self.heap.mark_accessed();
+		(base.clone(), Provenance::new(base, Expr::Value(SymTy::pointer(size as u128))))
+	}
+
+	pub fn process_scalar<'a>(&mut self, rand: Operand<'tcx>, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) -> Expr {
+		let ty = typing.operand_ty(&rand);
+		match self.process_operand(rand, nid, typing, sir).scalar() {
+			Some(expr) => expr,
+			None => fresh_scalar(ty, typing, sir)
+		}
+	}
+
+	/*
+	 * Reading a local that has not been assigned since its storage began is reported, unless it is a
+	 * union, which passes on that it is uninitialized.
+	 */
+	pub fn read<'a>(&mut self, plc: &Place<'tcx>, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) -> Value {
+		match self.resolve(plc, true, nid, typing, sir) {
+			Target::Slot(plc) => {
+				if base_local(&plc).map_or(false, |lcl| self.uninit.contains(&lcl)) {
+					let ty = typing.place_ty(&plc);
+					if typing.is_union(ty) {
+						return Value::Uninit;
+					}
+					if typing.size_of(ty) != Some(0) {
+						report(Expr::Value(SymTy::from_boolean(true)), ObligationKind::UninitRead, nid, sir);
+					}
+				}
+				self.slot_mut(&plc, typing, sir).value()
+			},
+			Target::Heap{addr, prov, ty, ..} => {
+				self.access(&prov, &addr, ty, false, nid, typing, sir);
+				self.heap.load_value(addr, ty, nid, typing, sir)
+			}
+		}
+	}
+
+	/*
+	 * The value in the return place, which is handed to the caller as it is, so that returning an
+	 * uninitialized value is not itself a read of it.
+	 */
+	pub fn return_value<'a>(&mut self, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) -> Value {
+		let ret = Local::from(0 as usize);
+		if self.uninit.contains(&ret) {
+			Value::Uninit
+		} else {
+			self.read(&local_place(ret), nid, typing, sir)
+		}
+	}
+
+	pub fn write<'a>(&mut self, plc: &Place<'tcx>, val: Value, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) {
+		match self.resolve(plc, true, nid, typing, sir) {
+			Target::Slot(plc) => {
+				if let (Value::Uninit, Place::Base(PlaceBase::Local(lcl))) = (&val, &plc) {
+					self.uninit.insert(*lcl);
+				} else if let Some(lcl) = base_local(&plc) {
+					self.uninit.remove(&lcl);
+				}
+				self.slot_mut(&plc, typing, sir).assign(val, nid, sir)
+			},
+			Target::Heap{addr, prov, ty, ..} => {
+				self.access(&prov, &addr, ty, true, nid, typing, sir);
+				self.heap.store_value(addr, ty, val, nid, typing, sir)
+			}
+		}
+	}
+
+	pub fn havoc<'a>(&mut self, plc: &Place<'tcx>, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) {
+		match self.resolve(plc, true, nid, typing, sir) {
+			Target::Slot(plc) => self.slot_mut(&plc, typing, sir).havoc(sir),
+			Target::Heap{addr, prov, ty, ..} => {
+				self.access(&prov, &addr, ty, true, nid, typing, sir);
+				let val = fresh_value(ty, typing, sir);
+				self.heap.store_value(addr, ty, val, nid, typing, sir);
+			}
+		}
+	}
+
+	pub fn leaves<'a>(&mut self, plc: &Place<'tcx>, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) -> Vec<Name> {
+		let ty = typing.place_ty(plc);
+		let val = self.read(plc, nid, typing, sir);
+		Memory::flatten_as(val, ty, nid, typing, sir)
+	}
+
+	/*
+	 * Overwrites plc with a fresh value and returns its leaves, for results that are related to
+	 * their inputs by a summary rather than by an expression.
+	 */
+	pub fn fresh_leaves<'a>(&mut self, plc: &Place<'tcx>, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) -> Vec<Name> {
+		let slot = Slot::fresh(typing.place_ty(plc), typing, None, sir);
+		let mut total = Vec::new();
+		slot.leaves(&mut total);
+		self.write(plc, slot.value(), nid, typing, sir);
+		total
+	}
+
+	pub fn discriminant<'a>(&mut self, plc: &Place<'tcx>, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) -> Value {
+		match self.resolve(plc, true, nid, typing, sir) {
+			Target::Slot(plc) => match self.slot_mut(&plc, typing, sir) {
+				Slot::Enum{discr, ..} => Value::Scalar(Expr::Ref(*discr)),
+				_ => Value::Opaque
+			},
+			Target::Heap{addr, ty, ..} => match ty.sty {
+				TyKind::Adt(def, _) if def.is_enum() => Value::Scalar(self.heap.load_discriminant(addr, def, nid, typing, sir)),
+				_ => Value::Opaque
+			}
+		}
+	}
+
+	pub fn set_discriminant<'a>(&mut self, plc: &Place<'tcx>, variant: VariantIdx, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) {
+		if let TyKind::Adt(def, _) = typing.place_ty(plc).sty {
+			let val = Expr::Value(typing.discriminant(def, variant));
+			match self.resolve(plc, true, nid, typing, sir) {
+				Target::Slot(plc) => {
+					if let Some(lcl) = base_local(&plc) {
+						self.uninit.remove(&lcl);
+					}
+					if let Slot::Enum{discr, ..} = self.slot_mut(&plc, typing, sir) {
+						assign_scalar(discr, val, nid, sir);
+					}
+				},
+				Target::Heap{addr, ..} => self.heap.store_discriminant(addr, def, val, typing)
+			}
+		}
+	}
+
+	pub fn len<'a>(&mut self, plc: &Place<'tcx>, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) -> Value {
+		if let Some(len) = typing.array_len(typing.place_ty(plc)) {
+			return Value::Scalar(Expr::Value(SymTy::pointer(len as u128)));
+		}
+
+		match self.resolve(plc, false, nid, typing, sir) {
+			Target::Heap{len: Some(len), ..} => Value::Scalar(len),
+			_ => Value::Scalar(Expr::Ref(sir.add_declaration(Declaration::of_sort(typing.usize_sort()))))
+		}
+	}
+
+	/*
+	 * The address of plc along with the allocation it lies in. Taking the address of a local moves
+	 * it into the heap.
+	 */
+	pub fn address_of<'a>(&mut self, plc: &Place<'tcx>, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) -> Value {
+		match self.resolve(plc, false, nid, typing, sir) {
+			Target::Heap{addr, prov, len: Some(len), ..} => Value::Slice(addr, len, prov),
+			Target::Heap{addr, prov, ..} => Value::Pointer(addr, prov),
+			Target::Slot(_) => match base_local(plc) {
+				Some(lcl) => {
+					self.escape(lcl, nid, typing, sir);
+					self.address_of(plc, nid, typing, sir)
+				},
+				None => Value::Pointer(self.fresh_pointer(nid, sir), Provenance::unknown())
+			}
+		}
+	}
+
+	/*
+	 * Takes a reference of kind to plc. Shared references to memory with interior mutability may
+	 * write to it, as may two-phase borrows, which are shared until they are first used.
+	 */
+	pub fn borrow<'a>(&mut self, plc: &Place<'tcx>, kind: BorrowKind, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) -> Value {
+		let perm = match kind {
+			BorrowKind::Mut{allow_two_phase_borrow: true} => Permission::SharedReadWrite,
+			BorrowKind::Mut{..} | BorrowKind::Unique => Permission::Unique,
+			BorrowKind::Shared | BorrowKind::Shallow if typing.is_freeze(typing.place_ty(plc)) => Permission::SharedReadOnly,
+			BorrowKind::Shared | BorrowKind::Shallow => Permission::SharedReadWrite
+		};
+		let ptr = self.address_of(plc, nid, typing, sir);
+		self.retag(ptr, typing.place_ty(plc), perm, nid, typing, sir)
+	}
+
+	/*
+	 * Casts a reference of type from_ty into a raw pointer, which is given a tag of its own that may
+	 * write unless the reference is shared and its pointee has no interior mutability.
+	 */
+	pub fn derive_raw<'a>(&mut self, ptr: Value, from_ty: Ty<'tcx>, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) -> Value {
+		let pointee = from_ty.builtin_deref(true).unwrap();
+		let perm = match pointee.mutbl {
+			Mutability::MutImmutable if typing.is_freeze(pointee.ty) => Permission::SharedReadOnly,
+			_ => Permission::SharedReadWrite
+		};
+		self.retag(ptr, pointee.ty, perm, nid, typing, sir)
+	}
+
+	fn retag<'a>(&mut self, ptr: Value, ty: Ty<'tcx>, perm: Permission, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) -> Value {
+		let size = match typing.access_size(ty) {
+			Some(size) => size,
+			None => return ptr
+		};
+		match ptr {
+			Value::Pointer(addr, prov) => {
+				let prov = self.heap.reborrow(prov, addr.clone(), size, perm, nid, sir);
+				Value::Pointer(addr, prov)
+			},
+			Value::Slice(addr, len, prov) => {
+				let prov = self.heap.reborrow(prov, addr.clone(), size, perm, nid, sir);
+				Value::Slice(addr, len, prov)
+			},
+			ptr => ptr
+		}
+	}
+
+	/*
+	 * Accesses the value of type ty at addr through a pointer of provenance prov.
+	 */
+	fn access<'a>(&mut self, prov: &Provenance, addr: &Expr, ty: Ty<'tcx>, write: bool, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) {
+		if let Some(size) = typing.access_size(ty) {
+			self.heap.access(prov, addr, size, write, nid, sir);
+		}
+	}
+
+	pub fn load_at<'a>(&mut self, ptr: Value, ty: Ty<'tcx>, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) -> Value {
+		let prov = ptr.provenance();
+		let addr = self.deref(ptr, ty, true, nid, typing, sir);
+		self.access(&prov, &addr, ty, false, nid, typing, sir);
+		self.heap.load_value(addr, ty, nid, typing, sir)
+	}
+
+	pub fn store_at<'a>(&mut self, ptr: Value, ty: Ty<'tcx>, val: Value, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) {
+		let prov = ptr.provenance();
+		let addr = self.deref(ptr, ty, true, nid, typing, sir);
+		self.access(&prov, &addr, ty, true, nid, typing, sir);
+		self.heap.store_value(addr, ty, val, nid, typing, sir);
+	}
+
+	pub fn fresh_pointer(&mut self, nid: NodeId, sir: &mut Sir) -> Expr {
+		let ptr = sir.add_declaration(Declaration::of_sort(SymTy::pointer(0)));
+		sir.add_expr_to_node(nid, non_null(Expr::Ref(ptr)));
+		Expr::Ref(ptr)
+	}
+
+	/*
+	 * Reinterprets val, a value of type from_ty, as a value of type to_ty by passing it through a
+	 * fresh allocation, so that the result is checked like any other read.
+	 */
+	pub fn transmute<'a>(&mut self, val: Value, from_ty: Ty<'tcx>, to_ty: Ty<'tcx>, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) -> Value {
+		let (addr, _) = self.allocation(from_ty, nid, typing, sir);
+		self.heap.store_value(addr.clone(), from_ty, val, nid, typing, sir);
+		self.heap.load_value(addr, to_ty, nid, typing, sir)
+	}
+
+	/*
+	 * A new heap allocation for a value of type ty, as made by box.
+	 */
+	pub fn allocate<'a>(&mut self, ty: Ty<'tcx>, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) -> Value {
+		let (base, prov) = self.allocation(ty, nid, typing, sir);
+		Value::Pointer(base, prov)
+	}
+
+	/*
+	 * A fresh allocation for a value of type ty, or a fresh address in an allocation that is not
+	 * known if the layout of ty is not.
+	 */
+	fn allocation<'a>(&mut self, ty: Ty<'tcx>, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) -> (Expr, Provenance) {
+		match (typing.size_of(ty), typing.align_of(ty)) {
+			(Some(size), Some(align)) => {
+				let base = self.heap.allocate(size, align, nid, sir);
+				(base.clone(), Provenance::new(base, Expr::Value(SymTy::pointer(size as u128))))
+			},
+			_ => (self.fresh_pointer(nid, sir), Provenance::unknown())
+		}
+	}
+
+	/*
+	 * Runs the drop glue of plc. Boxes are freed after their contents are dropped, and a type with a
+	 * Drop impl of its own may free anything it can reach.
+	 */
+	pub fn drop_place<'a>(&mut self, plc: &Place<'tcx>, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) {
+		let ty = typing.place_ty(plc);
+		if typing.needs_drop(ty) {
+			let val = self.read(plc, nid, typing, sir);
+			self.drop_value(val, ty, Expr::Value(SymTy::from_boolean(true)), nid, typing, sir);
+		}
+	}
+
+	pub fn drop_at<'a>(&mut self, ptr: Value, ty: Ty<'tcx>, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) {
+		if typing.needs_drop(ty) {
+			let val = self.load_at(ptr, ty, nid, typing, sir);
+			self.drop_value(val, ty, Expr::Value(SymTy::from_boolean(true)), nid, typing, sir);
+		}
+	}
+
+	/*
+	 * Drops val, a value of type ty, whenever cond holds. Only the fields of the variant an enum is
+	 * in are dropped.
+	 */
+	fn drop_value<'a>(&mut self, val: Value, ty: Ty<'tcx>, cond: Expr, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) {
+		if !typing.needs_drop(ty) {
+			return;
+		}
+
+		if ty.is_box() {
+			let pointee = ty.boxed_ty();
+			if typing.needs_drop(pointee) {
+				let contents = self.load_at(val.clone(), pointee, nid, typing, sir);
+				self.drop_value(contents, pointee, cond.clone(), nid, typing, sir);
+			}
+			let size = match (typing.access_size(pointee), val.length(), typing.slice_elem(pointee).and_then(|elem_ty| typing.size_of(elem_ty))) {
+				(Some(size), _, _) => Expr::Value(SymTy::pointer(size as u128)),
+				(None, Some(len), Some(elem_size)) => Expr::BinOp(Rator::Mul, Box::new(len), Box::new(Expr::Value(SymTy::pointer(elem_size as u128)))),
+				_ => Expr::Value(SymTy::pointer(0))
+			};
+			self.free(val, size, cond, nid, sir);
+			return;
+		}
+
+		match ty.sty {
+			TyKind::Adt(def, _) if typing.has_dtor(def) => self.havoc_heap(),
+			TyKind::Adt(def, substs) if def.is_enum() => match val {
+				Value::Enum(discr, variants) => for ((idx, variant), val) in def.variants.iter_enumerated().zip(variants.into_iter()) {
+					let active = Expr::BinOp(Rator::Eq, Box::new(discr.clone()), Box::new(Expr::Value(typing.discriminant(def, idx))));
+					let cond = Expr::BinOp(Rator::And, Box::new(cond.clone()), Box::new(active));
+					if let Value::Aggregate(fields) = val {
+						for (fty, field) in typing.variant_tys(variant, substs).into_iter().zip(fields.into_iter()) {
+							self.drop_value(field, fty, cond.clone(), nid, typing, sir);
+						}
+					}
+				},
+				_ => self.havoc_heap()
+			},
+			_ => match (val, typing.field_tys(ty)) {
+				(Value::Aggregate(fields), Some(tys)) => for (fty, field) in tys.into_iter().zip(fields.into_iter()) {
+					self.drop_value(field, fty, cond.clone(), nid, typing, sir);
+				},
+				_ => self.havoc_heap()
+			}
+		}
+	}
+
+	/*
+	 * Frees the size bytes at ptr whenever cond holds, which must not have been freed already. A
+	 * pointer that is not modelled frees nothing that is known.
+	 */
+	pub fn free(&mut self, ptr: Value, size: Expr, cond: Expr, nid: NodeId, sir: &mut Sir) {
+		let addr = match ptr.scalar() {
+			Some(addr) => name_of(addr, SymTy::pointer(0), nid, sir),
+			None => return
+		};
+		if let Some(freed) = self.heap.freed_base(&Expr::Ref(addr)) {
+			let violation = Expr::BinOp(Rator::And, Box::new(cond.clone()), Box::new(freed));
+			sir.add_property_to_declaration(addr, MirVariableProp::Obligation{kind: ObligationKind::DoubleFree, node: nid, violation});
+		}
+		self.heap.free(cond, Expr::Ref(addr), size);
+	}
+
+	/*
+	 * Attaches to every pointer that the return place can hold the condition under which it was
+	 * derived from a local of this frame, whose storage is gone once the frame returns.
+	 */
+	pub fn check_dangling_return<'a>(&mut self, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) {
+		if self.stack.is_empty() {
+			return;
+		}
+
+		let ret = local_place(Local::from(0 as usize));
+		let val = self.read(&ret, nid, typing, sir);
+		let mut pointers = Vec::new();
+		pointers_in(val, typing.place_ty(&ret), Expr::Value(SymTy::from_boolean(true)), typing, &mut pointers);
+		for (cond, prov) in pointers {
+			let base = name_of(prov.base(), SymTy::pointer(0), nid, sir);
+			let into_stack = self.stack.iter().map(|local| Expr::BinOp(Rator::Eq, Box::new(Expr::Ref(base)), Box::new(local.clone())))
+				.fold(Expr::Value(SymTy::from_boolean(false)), |x, y| Expr::BinOp(Rator::Or, Box::new(x), Box::new(y)));
+			let violation = Expr::BinOp(Rator::And, Box::new(cond), Box::new(into_stack));
+			sir.add_property_to_declaration(base, MirVariableProp::Obligation{kind: ObligationKind::DanglingReturn, node: nid, violation});
+		}
+	}
+
+	/*
+	 * Holds when ptr cannot have come from a live Box: it points into a local of this frame, past the
+	 * start of a known allocation, or at memory that has been freed.
+	 */
+	pub fn not_boxed(&self, ptr: &Value) -> Expr {
+		let (addr, base) = match ptr.clone().scalar() {
+			Some(addr) => (addr, ptr.provenance().base()),
+			None => return Expr::Value(SymTy::from_boolean(false))
+		};
+		let on_stack = self.stack.iter().map(|local| Expr::BinOp(Rator::Eq, Box::new(base.clone()), Box::new(local.clone())))
+			.fold(Expr::Value(SymTy::from_boolean(false)), |x, y| Expr::BinOp(Rator::Or, Box::new(x), Box::new(y)));
+		let interior = Expr::BinOp(Rator::And, Box::new(non_null(base.clone())), Box::new(Expr::BinOp(Rator::NotEqual, Box::new(addr.clone()), Box::new(base))));
+		let freed = self.heap.freed_base(&addr).unwrap_or(Expr::Value(SymTy::from_boolean(false)));
+		Expr::BinOp(Rator::Or, Box::new(Expr::BinOp(Rator::Or, Box::new(on_stack), Box::new(interior))), Box::new(freed))
+	}
+
+	/*
+	 * Flattens val into the leaves of a fresh value of type ty, so that it lines up with a summary's
+	 * parameters regardless of how much of val's structure is known.
+	 */
+	pub fn flatten_as<'a>(val: Value, ty: Ty<'tcx>, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) -> Vec<Name> {
+		let mut slot = Slot::fresh(ty, typing, None, sir);
+		slot.assign(val, nid, sir);
+		let mut total = Vec::new();
+		slot.leaves(&mut total);
+		total
+	}
+
+	fn escape<'a>(&mut self, lcl: Local, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) {
+		let ty = typing.local_ty(lcl);
+		let plc = local_place(lcl);
+		let val = self.slot_mut(&plc, typing, sir).value();
+		let (addr, prov) = self.allocation(ty, nid, typing, sir);
+		self.assignments.remove(&plc);
+		if self.uninit.remove(&lcl) {
+			if let Some(size) = typing.size_of(ty) {
+				self.heap.deinit(addr.clone(), size);
+			}
+		} else {
+			self.heap.store_value(addr.clone(), ty, val, nid, typing, sir);
+		}
+		if lcl != Local::from(0 as usize) {
+			self.stack.push(prov.base());
+		}
+		self.addresses.insert(lcl, (addr, prov));
+	}
+
+	/*
+	 * Names the pointer being dereferenced so that properties of the dereference can be attached to
+	 * it. When the pointee, of type ty, is accessed rather than only addressed, the dereference is
+	 * marked at nid and must stay within the allocation the pointer was derived from.
+	 */
+	fn deref<'a>(&mut self, ptr: Value, ty: Ty<'tcx>, mark: bool, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) -> Expr {
+		let prov = ptr.provenance();
+		let addr = ptr.scalar().unwrap_or_else(|| Expr::Ref(sir.add_declaration(Declaration::of_sort(SymTy::pointer(0)))));
+		let name_of_current_deref = name_of(addr, SymTy::pointer(0), nid, sir);
+		if mark {
+			sir.add_property_to_declaration(name_of_current_deref, MirVariableProp::IsDerefed(nid));
+			if let Some(len) = typing.access_size(ty) {
+				let violation = prov.out_of_bounds(&Expr::Ref(name_of_current_deref), len);
+				sir.add_property_to_declaration(name_of_current_deref, MirVariableProp::Obligation{kind: ObligationKind::OutOfBounds, node: nid, violation});
+			}
+			if let Some(violation) = self.heap.freed_at(&Expr::Ref(name_of_current_deref)) {
+				sir.add_property_to_declaration(name_of_current_deref, MirVariableProp::Obligation{kind: ObligationKind::UseAfterFree, node: nid, violation});
+			}
+		}
+		self.heap.mark_accessed();
+		Expr::Ref(name_of_current_deref)
+	}
+
+	/*
+	 * A raw pointer may only be dereferenced at an address aligned for its pointee, of type ty. The
+	 * alignment of a pointee whose layout is not known is not checked.
+	 */
+	pub fn check_alignment<'a>(&mut self, addr: &Expr, ty: Ty<'tcx>, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) {
+		if let Some(align) = typing.access_size(ty).and(typing.align_of(ty)) {
+			let nm = name_of(addr.clone(), SymTy::pointer(0), nid, sir);
+			let violation = Expr::UnOp(Rator::Not, Box::new(aligned(Expr::Ref(nm), align)));
+			sir.add_property_to_declaration(nm, MirVariableProp::Obligation{kind: ObligationKind::Misaligned, node: nid, violation});
+		}
+	}
+
+	fn resolve<'a>(&mut self, plc: &Place<'tcx>, mark: bool, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) -> Target<'tcx> {
+		match plc {
+			Place::Base(PlaceBase::Local(lcl)) => match self.addresses.get(lcl) {
+				Some((addr, prov)) => Target::Heap{addr: addr.clone(), prov: prov.clone(), ty: typing.local_ty(*lcl), variant: None, len: None},
+				None => Target::Slot(plc.clone())
+			},
+			Place::Base(PlaceBase::Static(st)) => {
+				let (addr, prov) = self.static_address(&st.kind, st.ty, nid, typing, sir);
+				Target::Heap{addr, prov, ty: st.ty, variant: None, len: None}
+			},
+			Place::Projection(proj) => match proj.elem {
+				ProjectionElem::Deref => {
+					let ptr = self.read(&proj.base, nid, typing, sir);
+					let (ty, prov, len) = (typing.place_ty(plc), ptr.provenance(), ptr.length());
+					let addr = self.deref(ptr, ty, mark, nid, typing, sir);
+					if mark && typing.place_ty(&proj.base).is_unsafe_ptr() {
+						self.check_alignment(&addr, ty, nid, typing, sir);
+					}
+					Target::Heap{addr, prov, ty, variant: None, len}
+				},
+				ProjectionElem::Field(field, _) => match self.resolve(&proj.base, mark, nid, typing, sir) {
+					Target::Slot(_) if typing.is_union(typing.place_ty(&proj.base)) => match base_local(plc) {
+						Some(lcl) => {
+							self.escape(lcl, nid, typing, sir);
+							self.resolve(plc, mark, nid, typing, sir)
+						},
+						None => Target::unknown(typing.place_ty(plc), sir)
+					},
+					Target::Slot(_) => Target::Slot(plc.clone()),
+					Target::Heap{addr, prov, ty, variant, ..} => match typing.field_offset(ty, variant, field.index()) {
+						Some(bytes) => Target::Heap{addr: offset(addr, bytes), prov, ty: typing.place_ty(plc), variant: None, len: None},
+						None => Target::unknown(typing.place_ty(plc), sir)
+					}
+				},
+				ProjectionElem::Downcast(_, variant) => match self.resolve(&proj.base, mark, nid, typing, sir) {
+					Target::Slot(_) => Target::Slot(plc.clone()),
+					Target::Heap{addr, prov, ty, len, ..} => Target::Heap{addr, prov, ty, variant: Some(variant), len}
+				},
+				_ => match self.resolve(&proj.base, mark, nid, typing, sir) {
+					Target::Slot(_) => match base_local(plc) {
+						Some(lcl) => {
+							self.escape(lcl, nid, typing, sir);
+							self.resolve(plc, mark, nid, typing, sir)
+						},
+						None => Target::unknown(typing.place_ty(plc), sir)
+					},
+					Target::Heap{addr, prov, ty, len, ..} => {
+						let len = len.or(typing.array_len(ty).map(|len| Expr::Value(SymTy::pointer(len as u128))));
+						self.project_elements(&proj.elem, addr, prov, ty, len, typing.place_ty(plc), mark, nid, typing, sir)
+					}
+				}
+			}
+		}
+	}
+
+	/*
+	 * Selects from the array or slice of type ty at addr, which holds len elements. Arrays are only
+	 * indexed in the heap, so indexing a local moves it there first. An index read from a local
+	 * must be below len when the element is accessed. A slice whose length is not known gets a
+	 * fresh one when an element is counted from its end.
+	 */
+	fn project_elements<'a>(&mut self, elem: &PlaceElem<'tcx>, addr: Expr, prov: Provenance, ty: Ty<'tcx>, len: Option<Expr>, plc_ty: Ty<'tcx>, mark: bool, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) -> Target<'tcx> {
+		let elem = typing.elem_ty(ty).and_then(|elem_ty| typing.size_of(elem_ty).map(|stride| (elem_ty, stride)));
+		let (elem_ty, stride) = match elem {
+			Some(elem) => elem,
+			None => return Target::unknown(plc_ty, sir)
+		};
+		let at = |idx: Expr| Expr::BinOp(Rator::Add, Box::new(addr.clone()), Box::new(Expr::BinOp(Rator::Mul, Box::new(idx), Box::new(Expr::Value(SymTy::pointer(stride as u128))))));
+		match elem {
+			ProjectionElem::Index(idx) => {
+				let mut idx = match self.read(&local_place(*idx), nid, typing, sir).scalar() {
+					Some(idx) => idx,
+					None => fresh_scalar(typing.tcx().types.usize, typing, sir)
+				};
+				if let (true, Some(len)) = (mark, &len) {
+					let nm = name_of(idx, typing.usize_sort(), nid, sir);
+					let violation = Expr::BinOp(Rator::GreaterEqual, Box::new(Expr::Ref(nm)), Box::new(len.clone()));
+					sir.add_property_to_declaration(nm, MirVariableProp::Obligation{kind: ObligationKind::OutOfBounds, node: nid, violation});
+					idx = Expr::Ref(nm);
+				}
+				Target::Heap{addr: at(idx), prov, ty: elem_ty, variant: None, len: None}
+			},
+			ProjectionElem::ConstantIndex{offset, from_end, ..} => {
+				let offset = Expr::Value(SymTy::pointer(*offset as u128));
+				let idx = if *from_end {
+					let len = len.unwrap_or_else(|| fresh_scalar(typing.tcx().types.usize, typing, sir));
+					Expr::BinOp(Rator::Sub, Box::new(len), Box::new(offset))
+				} else {
+					offset
+				};
+				Target::Heap{addr: at(idx), prov, ty: elem_ty, variant: None, len: None}
+			},
+			ProjectionElem::Subslice{from, to} => {
+				let (from, to) = (Expr::Value(SymTy::pointer(*from as u128)), Expr::Value(SymTy::pointer(*to as u128)));
+				let len = len.map(|len| Expr::BinOp(Rator::Sub, Box::new(Expr::BinOp(Rator::Sub, Box::new(len), Box::new(from.clone()))), Box::new(to)));
+				Target::Heap{addr: at(from), prov, ty: plc_ty, variant: None, len}
+			},
+			_ => Target::unknown(plc_ty, sir)
+		}
+	}
+
+	/*
+	 * The slot of plc, a local or a field or variant of one. Any other place gets a slot of its own
+	 * that starts out unknown.
+	 */
+	fn slot_mut<'a>(&mut self, plc: &Place<'tcx>, typing: Typing<'a,'tcx>, sir: &mut Sir) -> &mut Slot {
+		if let Place::Projection(proj) = plc {
+			match proj.elem {
+				ProjectionElem::Field(field, _) => return self.slot_mut(&proj.base, typing, sir).field_mut(field.index()),
+				ProjectionElem::Downcast(_, variant) => return self.slot_mut(&proj.base, typing, sir).variant_mut(variant.index()),
+				_ => ()
+			}
+		}
+
+		if !self.assignments.contains_key(plc) {
+			let slot = Slot::unknown(typing.place_ty(plc), typing, None, sir);
+			self.assignments.insert(plc.clone(), slot);
+		}
+		self.assignments.get_mut(plc).unwrap()
+	}
+
+	pub fn from_args<'a>(did: DefId, typing: Typing<'a,'tcx>, sir: &mut Sir) -> Memory<'tcx> {
+		let mir = typing.get_mir();
+		let mut memory = Memory::new();
+
+		let ret = Local::from(0 as usize);
+		memory.add_new_var(ret, typing, sir, Some((did, ret, vec![])));
+		for arg in mir.args_iter() {
+			let ty = typing.local_ty(arg);
+			let slot = Slot::fresh(ty, typing, Some((did, arg, vec![])), sir);
+			let mut unknown = Vec::new();
+			slot.unknown_provenance(ty, typing, &mut unknown);
+			for expr in unknown {
+				sir.add_assumption(expr);
+			}
+			memory.assignments.insert(local_place(arg), slot);
+		}
+
+		memory
+	}
+
+	/*
+	 * The values the arguments were entered with, for contracts stated over them.
+	 */
+	pub fn arg_values<'a>(&self, typing: Typing<'a,'tcx>) -> Vec<(Value, Ty<'tcx>)> {
+		typing.get_mir().args_iter().map(|arg| (self.assignments.get(&local_place(arg)).unwrap().value(), typing.local_ty(arg))).collect()
+	}
+
+	pub fn arg_leaves<'a>(&self, typing: Typing<'a,'tcx>) -> Vec<Name> {
+		let mut total = Vec::new();
+		for arg in typing.get_mir().args_iter() {
+			self.assignments.get(&local_place(arg)).unwrap().leaves(&mut total);
+		}
+		total
+	}
+
+	pub fn add_new_var<'a>(&mut self, lcl: Local, typing: Typing<'a,'tcx>, sir: &mut Sir, loc: Option<VarLocation>) {
+		let slot = Slot::unknown(typing.local_ty(lcl), typing, loc, sir);
+		self.addresses.remove(&lcl);
+		self.uninit.insert(lcl);
+		self.assignments.insert(local_place(lcl), slot);
+	}
+
+	/*
+	 * Ends the storage of lcl, freeing its allocation if its address was taken.
+	 */
+	pub fn remove_var(&mut self, lcl: Local) {
+		if let Some((addr, prov)) = self.addresses.remove(&lcl) {
+			self.heap.free(Expr::Value(SymTy::from_boolean(true)), addr, prov.size());
+		}
+		self.uninit.remove(&lcl);
+		self.assignments.remove(&local_place(lcl));
+	}
+
+	/*
+	 * Ends the storage of every local, as when the frame returns.
+	 */
+	pub fn remove_all_vars(&mut self) {
+		let escaped: Vec<Local> = self.addresses.keys().cloned().collect();
+		for lcl in escaped {
+			self.remove_var(lcl);
+		}
+		self.uninit.clear();
+		self.assignments.clear();
+	}
+}
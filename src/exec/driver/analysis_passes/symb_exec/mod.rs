@@ -2,14 +2,11 @@
 use rustc::mir::Terminator;
 use std::borrow::Cow;
 use rustc::mir::Rvalue;
-use rustc::mir::interpret::ConstValue;
-use rustc::mir::interpret::Scalar;
 use rustc::ty::Ty;
-use rustc::mir::LocalDecl;
 use rustc::mir::Local;
 use rustc::mir::BasicBlock;
 use rustc::mir::Place;
-use std::collections::HashMap;
+use std::collections::{HashMap,HashSet};
 use rustc::mir::Mir;
 use crate::exec::driver::analysis_passes::sir::Sir;
 use rustc::hir::def_id::DefId;
@@ -17,164 +14,174 @@ use rustc::mir::BasicBlockData;
 use super::sir::Name;
 use super::sir::NodeId;
 use rustc::mir::Statement;
-use rustc::mir::PlaceBase;
-use super::sir::Declaration;
 use rustc::mir::StatementKind;
-use rustc::mir::ProjectionElem;
+use rustc::mir::AggregateKind;
 use super::sir::Expr;
-use super::sir::MirVariableProp;
 use super::sir::Rator;
 use rustc::mir::Operand;
 use super::sir::SymTy;
 use rustc::mir::TerminatorKind;
+use rustc::ty::TyKind;
+use rustc::ty::TyCtxt;
+use rustc::ty::{Instance,InstanceDef,ParamEnv};
+use rustc::mir::BinOp;
+use rustc::mir::NullOp;
+use rustc::ty::subst::SubstsRef;
+use rustc::ty::layout::VariantIdx;
 use super::sir::Edge;
+use super::sir::Summary;
+use super::sir::BitWidth;
+use super::sir::ObligationKind;
+use rustc::mir::interpret::InterpError;
+use rustc::mir::AssertMessage;
+use rustc::mir::Field;
+use syntax_pos::Span;
+use syntax::ast;
+use syntax::ptr::P;
+
+mod memory;
+mod heap;
+mod intrinsics;
+mod models;
+mod contracts;
+use contracts::Contract;
+pub use contracts::{Assumptions,has_contract,contract_source,clause_source};
+use heap::Provenance;
+use memory::{Memory,Value,local_place,cast_pointer,fresh_value,fresh_scalar,report,MAX_ARRAY_LEN};
+pub use memory::Typing;
 
 
 const MAX_UNROLL: usize = 5;
+pub const MAX_CALL_DEPTH: usize = 3;
 
-#[derive(Clone)]
-struct Memory<'tcx> {
-	assignments: HashMap<Place<'tcx>,Name>,
-}
+type MirMap<'a,'tcx> = HashMap<Instance<'tcx>,&'a Mir<'tcx>>;
 
-impl <'tcx> Memory <'tcx> {
+/*
+ * Summaries are kept per instance, as the body of a generic function or trait method depends on
+ * its substitution.
+ */
+pub struct SummaryCache<'tcx> {
+	summaries: HashMap<Instance<'tcx>, Summary>,
+	in_progress: HashSet<Instance<'tcx>>
+}
 
-	fn process_operand(&mut self, rand: Operand<'tcx>, nid: NodeId, sir: &mut Sir) -> Expr {
-		match rand {
-			Operand::Copy(plc) => Expr::Ref(self.copy_val(&plc,nid,sir)),
-			Operand::Move(plc) => Expr::Ref(self.move_val(&plc,nid,sir)),
-			Operand::Constant(cst) => Expr::Value(SymTy::from_scalar(match cst.literal.val {
-				ConstValue::Scalar(sc) => match sc {
-					Scalar::Bits{bits, size:_} => bits,
-					_ => unimplemented!(),
-				},
-				_ => unimplemented!()
-			}, cst.ty))
-			
+impl <'tcx> SummaryCache<'tcx> {
+	pub fn new() -> SummaryCache<'tcx> {
+		SummaryCache {
+			summaries: HashMap::new(),
+			in_progress: HashSet::new()
 		}
 	}
 
-	fn new_assignment(&mut self, to: Place<'tcx>, nid: NodeId, sir: &mut Sir) -> Name {
-		let old_name = self.process_plc(&to, nid, sir);
-		let old_decl = sir.get_declaration(old_name);
-		let new_name = sir.add_declaration(old_decl.new_declaration());
-		self.assignments.insert(to, new_name);
-		new_name
+	pub fn insert(&mut self, instance: Instance<'tcx>, summary: Summary) {
+		self.summaries.insert(instance, summary);
 	}
 
-	fn move_val(&mut self, plc:  &Place<'tcx>,nid: NodeId, sir: &mut Sir) -> Name {
-		let _ = self.process_plc(plc, nid, sir);
-		self.assignments.remove(plc).unwrap()
-	}
-
-	fn copy_val(&mut self, plc:  &Place<'tcx>,nid: NodeId, sir: &mut Sir) -> Name {
-		let _ = self.process_plc(plc, nid, sir);
-		*self.assignments.get(plc).unwrap()
-	}
-
-	fn process_plc(&mut self, plc: &Place<'tcx>, nid: NodeId, sir: &mut Sir) -> Name  {
-		match plc {
-			Place::Base(_) => *self.assignments.get(plc).unwrap(),
-			Place::Projection(proj) => match proj.elem {
-				ProjectionElem::Deref => {
-					let name_of_current_deref = *self.assignments.get(&proj.base).unwrap();
-					sir.add_property_to_declaration(name_of_current_deref, MirVariableProp::IsDerefed(nid));
-					let name = sir.add_declaration(sir.get_declaration(name_of_current_deref).new_declaration());
-					self.assignments.insert(plc.clone(), name);
-					name
-				},
-				_ => unimplemented!()
+	fn get_or_compute<'a>(&mut self, instance: Instance<'tcx>, mirs: &MirMap<'a,'tcx>, tcx: TyCtxt<'a,'tcx,'tcx>) -> Option<&Summary> {
+		if !self.summaries.contains_key(&instance) {
+			if self.in_progress.contains(&instance) {
+				return None;
 			}
-		}
-	}
-
-	fn from_args(args: impl Iterator<Item = Local>, did: DefId, mir: &Mir, sir: &mut Sir) -> Memory<'tcx> {
-		
-		let mut assignments = HashMap::new();
-
-		let return_plc = Place::Base(PlaceBase::Local(Local::from(0 as usize)));
-		let return_decl = Declaration::decl_from(mir.local_decls[Local::from(0 as usize)].ty,Some((did,Local::from(0 as usize))));
-		let ret_name = sir.add_declaration(return_decl);
-		assignments.insert(return_plc, ret_name);
 
-		for arg in args {
-			let lcl = &mir.local_decls[arg];
-			let plc = Place::Base(PlaceBase::Local(arg));
-			let declaration = Declaration::decl_from(lcl.ty,Some((did,arg)));
-			let nm = sir.add_declaration(declaration);
-			assignments.insert(plc, nm);
-		}
-
-		Memory {
-			assignments
+			self.in_progress.insert(instance);
+			let (_, _, summary) = ExecutionContext::create_from_entry(instance, Vec::new(), mirs.clone(), self, tcx).evaluate();
+			self.in_progress.remove(&instance);
+			self.summaries.insert(instance, summary);
 		}
 
+		self.summaries.get(&instance)
 	}
+}
 
-	fn add_new_var(&mut self, plc: Place<'tcx>, ty: Ty<'tcx>,sir: &mut Sir, did: Option<(DefId,Local)>) {
-		let decl = Declaration::decl_from(ty,did);
-		self.assignments.insert(plc, sir.add_declaration(decl));
+pub fn callee_def_id<'tcx>(func: &Operand<'tcx>) -> Option<(DefId, SubstsRef<'tcx>)> {
+	if let Operand::Constant(cst) = func {
+		if let TyKind::FnDef(did, substs) = cst.ty.sty {
+			return Some((did, substs));
+		}
 	}
+	None
+}
 
-	fn remove_var(&mut self, plc: &Place<'tcx>) {
-		self.assignments.remove(&plc);
+/*
+ * The instance a call to func runs, which for a trait method is the one of the impl it resolves
+ * to. None for calls that do not resolve to an item of their own, such as shims.
+ */
+pub fn callee_instance<'a,'tcx>(func: &Operand<'tcx>, tcx: TyCtxt<'a,'tcx,'tcx>) -> Option<Instance<'tcx>> {
+	let (did, substs) = callee_def_id(func)?;
+	match Instance::resolve(tcx, ParamEnv::reveal_all(), did, substs) {
+		Some(instance @ Instance{def: InstanceDef::Item(_), ..}) => Some(instance),
+		_ => None
 	}
 }
 
-
+/*
+ * The instance of the function being executed is part of the location, as each substitution of a
+ * generic function and each impl of a trait method has a body of its own.
+ */
 #[derive(Clone,Hash,PartialEq,Eq)]
-struct Location {
-	mir_def: DefId,
+struct Location<'tcx> {
+	instance: Instance<'tcx>,
 	block: BasicBlock
 }
 
-impl Location {
-	fn new(mir_def: DefId,  block: BasicBlock) -> Location {
+impl <'tcx> Location<'tcx> {
+	fn new(instance: Instance<'tcx>,  block: BasicBlock) -> Location<'tcx> {
 		Location {
-			mir_def,
+			instance,
 			block
 		}
 	}
 
 	fn get_def_id(&self) -> DefId {
-		self.mir_def
+		self.instance.def_id()
 	}
 
-	fn get_block_data<'tcx>(&self, mirs: &HashMap<DefId,&'tcx Mir<'tcx>>) -> &'tcx BasicBlockData<'tcx> {
-		&mirs.get(&self.mir_def).unwrap().basic_blocks()[self.block]	
+	fn get_instance(&self) -> Instance<'tcx> {
+		self.instance
 	}
 
-	fn get_statements<'tcx>(&self,mirs: &HashMap<DefId,&'tcx Mir<'tcx>>) -> &Vec<Statement<'tcx>> {
+	fn get_block_data<'a>(&self, mirs: &MirMap<'a,'tcx>) -> &'a BasicBlockData<'tcx> {
+		&mirs.get(&self.instance).unwrap().basic_blocks()[self.block]	
+	}
+
+	fn get_statements<'a>(&self,mirs: &MirMap<'a,'tcx>) -> &'a Vec<Statement<'tcx>> {
 		&self.get_block_data(mirs).statements
 	}
 
-	fn get_local_decl<'tcx>(&self, lcl: Local, mir: &HashMap<DefId,&'tcx Mir<'tcx>>) -> &LocalDecl<'tcx> {
-		&mir.get(&self.mir_def).unwrap().local_decls[lcl]
+	fn get_mir<'a>(&self, mirs: &MirMap<'a,'tcx>) -> &'a Mir<'tcx> {
+		mirs.get(&self.instance).unwrap()
 	}
 
 
-	fn from_block(&self, block: BasicBlock) -> Location {
+	fn from_block(&self, block: BasicBlock) -> Location<'tcx> {
 		Location {
 			block,
-			mir_def: self.mir_def
+			instance: self.instance
 		}
 	}
 
 
 }
 
+#[derive(Clone)]
+struct CallSite<'tcx> {
+	location: Location<'tcx>,
+	destination: Place<'tcx>,
+	memory: Memory<'tcx>
+}
+
 struct Frame<'tcx> {
 	generator: Option<NodeId>,
 	precondition: Option<Expr>,
 	current_memory: Memory<'tcx>,
-	seen_counts: HashMap<Location, usize>,
-	current_loc: Location,
-	return_to: Vec<(Location)>
+	seen_counts: HashMap<Location<'tcx>, usize>,
+	current_loc: Location<'tcx>,
+	return_to: Vec<CallSite<'tcx>>
 } 
 
 impl <'tcx> Frame<'tcx> {
 
-	fn from_new_loc(&self, new_generator: NodeId,new_loc: Location, precondition: Option<Expr>, memory: Memory<'tcx>) -> Frame<'tcx> {
+	fn from_new_loc(&self, new_generator: NodeId,new_loc: Location<'tcx>, precondition: Option<Expr>, memory: Memory<'tcx>) -> Frame<'tcx> {
 		let mut seen_counts = self.seen_counts.clone();
 		seen_counts.insert(new_loc.clone(), seen_counts.get(&new_loc).unwrap_or(&0) + 1);
 		Frame {
@@ -187,33 +194,222 @@ impl <'tcx> Frame<'tcx> {
 		}
 	}
 
-	fn derive_next_frames(&mut self, nid: NodeId, term: &Terminator<'tcx>, sir: &mut Sir) -> impl Iterator<Item = Frame<'tcx>> {
+	fn typing<'a>(&self, mirs: &MirMap<'a,'tcx>, tcx: TyCtxt<'a,'tcx,'tcx>) -> Typing<'a,'tcx> {
+		Typing::new(self.current_loc.get_mir(mirs), tcx)
+	}
+
+	/*
+	 * Unwinding and dropping a generator end the path. A generator that yields may find anything in
+	 * the heap changed once it is resumed.
+	 */
+	fn derive_next_frames<'a>(&mut self, nid: NodeId, term: &Terminator<'tcx>, mirs: &MirMap<'a,'tcx>, tcx: TyCtxt<'a,'tcx,'tcx>, summaries: &mut SummaryCache<'tcx>, sir: &mut Sir) -> impl Iterator<Item = Frame<'tcx>> {
+		let typing = self.typing(mirs, tcx);
 		match &term.kind {
 			TerminatorKind::Goto {target} => if let Some(conv) = self.derive_goto(nid,*target) {
 				vec![conv]
 			} else {
 				vec![]
 			}.into_iter(),
-			TerminatorKind::Call {func:_,args:_,destination:_,cleanup:_,from_hir_call:_} => unimplemented!(),
-			TerminatorKind::SwitchInt{discr, switch_ty,values,targets} => self.derive_switch_int(nid,discr, switch_ty, values,targets.clone(),sir).into_iter(),
-			TerminatorKind::Assert{expected,cond,msg:_,target, cleanup:_} => {
+			TerminatorKind::Call {func,args,destination,cleanup:_,from_hir_call:_} => self.derive_call(nid, func, args, destination, term.source_info.span, mirs, tcx, summaries, sir).into_iter(),
+			TerminatorKind::SwitchInt{discr, switch_ty,values,targets} => self.derive_switch_int(nid,discr, switch_ty, values,targets.clone(),typing,sir).into_iter(),
+			TerminatorKind::Assert{expected,cond,msg,target, cleanup:_} => {
 				let test_val = SymTy::from_boolean(*expected);
-				let rand = self.current_memory.process_operand(cond.clone(), nid, sir);
+				let rand = self.current_memory.process_scalar(cond.clone(), nid, typing, sir);
 				let assert_expr = Expr::BinOp(Rator::Eq, Box::new(Expr::Value(test_val)), Box::new(rand));
-				sir.add_expr_to_node(nid,assert_expr);
-				if let Some(conv) = self.derive_goto(nid,*target) {
+				if let Some(kind) = arithmetic_panic(msg) {
+					report(Expr::UnOp(Rator::Not, Box::new(assert_expr.clone())), kind, nid, sir);
+				}
+				if let Some(conv) = self.block_to_frame(nid,*target,Some(assert_expr),self.current_memory.clone()) {
 				vec![conv]
 			} else {
 				vec![]
 			}.into_iter()},
-			TerminatorKind::Return => vec![].into_iter(),
-			_ => unimplemented!(),
+			TerminatorKind::Drop{location, target, unwind:_} => {
+				self.current_memory.drop_place(location, nid, typing, sir);
+				if let Some(conv) = self.derive_goto(nid, *target) {
+					vec![conv]
+				} else {
+					vec![]
+				}.into_iter()
+			},
+			TerminatorKind::DropAndReplace{location, value, target, unwind:_} => {
+				self.current_memory.drop_place(location, nid, typing, sir);
+				let val = self.current_memory.process_operand(value.clone(), nid, typing, sir);
+				self.current_memory.write(location, val, nid, typing, sir);
+				if let Some(conv) = self.derive_goto(nid, *target) {
+					vec![conv]
+				} else {
+					vec![]
+				}.into_iter()
+			},
+			TerminatorKind::Return => self.derive_return(nid, mirs, tcx, sir).into_iter(),
+			TerminatorKind::Unreachable => {
+				report(Expr::Value(SymTy::from_boolean(true)), ObligationKind::ReachedUnreachable, nid, sir);
+				vec![].into_iter()
+			},
+			TerminatorKind::FalseEdges{real_target, ..} | TerminatorKind::FalseUnwind{real_target, ..} => if let Some(conv) = self.derive_goto(nid, *real_target) {
+				vec![conv]
+			} else {
+				vec![]
+			}.into_iter(),
+			TerminatorKind::Yield{resume, ..} => {
+				self.current_memory.havoc_heap();
+				if let Some(conv) = self.derive_goto(nid, *resume) {
+					vec![conv]
+				} else {
+					vec![]
+				}.into_iter()
+			},
+			TerminatorKind::Resume | TerminatorKind::Abort | TerminatorKind::GeneratorDrop => vec![].into_iter()
 		}
 	}
 
-	fn derive_switch_int(&self, generator: NodeId, discr: &Operand<'tcx>, switch_ty: Ty, values: &Cow<'tcx,[u128]>, mut targets: Vec<BasicBlock>, sir: &mut Sir) -> Vec<Frame<'tcx>> {
+	/*
+	 * A call that never returns is reported when it is one the code promises is never reached. The
+	 * assume intrinsic is reported when its argument may be false, and holds from then on. A callee
+	 * with a contract is reported when its requires clauses may fail, and is not entered: its
+	 * result is anything satisfying its ensures clauses. A callee whose summary does not take the
+	 * arguments of the call one for one, as a closure called through its tupled arguments, is
+	 * neither summarized nor entered, and its result is anything.
+	 */
+	fn derive_call<'a>(&mut self, nid: NodeId, func: &Operand<'tcx>, args: &Vec<Operand<'tcx>>, destination: &Option<(Place<'tcx>, BasicBlock)>, span: Span, mirs: &MirMap<'a,'tcx>, tcx: TyCtxt<'a,'tcx,'tcx>, summaries: &mut SummaryCache<'tcx>, sir: &mut Sir) -> Vec<Frame<'tcx>> {
+		let callee = callee_def_id(func).map(|(did,_)| did);
+		let (dest, target) = match destination {
+			Some((dest, target)) => (dest.clone(), *target),
+			None => {
+				if callee.map_or(false, |did| promised_unreachable(did, span, tcx)) {
+					report(Expr::Value(SymTy::from_boolean(true)), ObligationKind::ReachedUnreachable, nid, sir);
+				}
+				return vec![];
+			}
+		};
+
+		let typing = self.typing(mirs, tcx);
+		if let Some(name) = callee.and_then(|did| intrinsics::intrinsic_name(did, tcx)) {
+			if let (true, Some(rand)) = (name == "assume", args.first()) {
+				let cond = self.current_memory.process_scalar(rand.clone(), nid, typing, sir);
+				report(Expr::UnOp(Rator::Not, Box::new(cond.clone())), ObligationKind::ReachedUnreachable, nid, sir);
+				self.current_memory.write(&dest, Value::Aggregate(vec![]), nid, typing, sir);
+				return self.block_to_frame(nid, target, Some(cond), self.current_memory.clone()).into_iter().collect();
+			}
+			let vals = args.iter().map(|rand| (self.current_memory.process_operand(rand.clone(), nid, typing, sir), typing.operand_ty(rand))).collect();
+			if let Some(val) = intrinsics::evaluate(&name, vals, typing.place_ty(&dest), &mut self.current_memory, nid, typing, sir) {
+				self.current_memory.write(&dest, val, nid, typing, sir);
+				return self.derive_goto(nid, target).into_iter().collect();
+			}
+		}
+
+		if let Some(path) = callee.and_then(|did| models::model_path(did, tcx)) {
+			let vals = args.iter().map(|rand| (self.current_memory.process_operand(rand.clone(), nid, typing, sir), typing.operand_ty(rand))).collect();
+			if let Some(val) = models::evaluate(&path, vals, typing.place_ty(&dest), &mut self.current_memory, nid, typing, sir) {
+				self.current_memory.write(&dest, val, nid, typing, sir);
+				return self.derive_goto(nid, target).into_iter().collect();
+			}
+		}
+
+		if let Some(contract) = callee.and_then(|did| Contract::of(did, tcx)) {
+			let vals: Vec<_> = args.iter().map(|rand| (self.current_memory.process_operand(rand.clone(), nid, typing, sir), typing.operand_ty(rand))).collect();
+			report(Expr::UnOp(Rator::Not, Box::new(contract.requires(vals.clone(), typing, tcx))), ObligationKind::PreconditionViolation, nid, sir);
+			if !args.iter().all(|rand| typing.is_plain(typing.operand_ty(rand))) {
+				self.current_memory.havoc_heap();
+			}
+			let dest_ty = typing.place_ty(&dest);
+			let result = fresh_value(dest_ty, typing, sir);
+			self.current_memory.write(&dest, result.clone(), nid, typing, sir);
+			let ensures = contract.ensures(vals, (result, dest_ty), typing, tcx);
+			return self.block_to_frame(nid, target, Some(ensures), self.current_memory.clone()).into_iter().collect();
+		}
+
+		let callee_loc = callee_instance(func, tcx).filter(|instance| mirs.contains_key(instance))
+			.map(|instance| Location::new(instance, BasicBlock::from(0 as usize)));
+		let summary = callee_loc.as_ref()
+			.and_then(|loc| summaries.get_or_compute(loc.get_instance(), mirs, tcx))
+			.filter(|summary| !summary.touches_heap());
+
+		let mut spread = false;
+		if let Some(summary) = summary {
+			let callee_mir = callee_loc.as_ref().unwrap().get_mir(mirs);
+			let mut vals = Vec::new();
+			for (lcl, rand) in callee_mir.args_iter().zip(args.iter()) {
+				let val = self.current_memory.process_operand(rand.clone(), nid, typing, sir);
+				vals.extend(Memory::flatten_as(val, callee_mir.local_decls[lcl].ty, nid, typing, sir).into_iter().map(Expr::Ref));
+			}
+
+			let dest_names = self.current_memory.fresh_leaves(&dest, nid, typing, sir);
+			match summary.instantiate(sir, nid, vals, dest_names) {
+				Some(Some(relation)) => return self.block_to_frame(nid, target, Some(relation), self.current_memory.clone()).into_iter().collect(),
+				Some(None) => return vec![],
+				None => spread = true
+			}
+		}
+
+		match callee_loc {
+			Some(ref loc) if !spread && self.return_to.len() < MAX_CALL_DEPTH && self.should_examine(loc) => {
+				let callee_typing = Typing::new(loc.get_mir(mirs), tcx);
+				let vals: Vec<Value> = args.iter().map(|rand| self.current_memory.process_operand(rand.clone(), nid, typing, sir)).collect();
+				let mut callee_memory = Memory::sharing_heap(&self.current_memory);
+				for (lcl, val) in callee_typing.get_mir().args_iter().zip(vals.into_iter()) {
+					callee_memory.write(&local_place(lcl), val, nid, callee_typing, sir);
+				}
+
+				let site = CallSite {
+					location: self.current_loc.from_block(target),
+					destination: dest,
+					memory: self.current_memory.clone()
+				};
+				let mut frame = self.from_new_loc(nid, loc.clone(), None, callee_memory);
+				frame.return_to.push(site);
+				vec![frame]
+			},
+			_ => {
+				for rand in args {
+					let _ = self.current_memory.process_operand(rand.clone(), nid, typing, sir);
+				}
+				if !args.iter().all(|rand| typing.is_plain(typing.operand_ty(rand))) {
+					self.current_memory.havoc_heap();
+				}
+				self.current_memory.havoc(&dest, nid, typing, sir);
+				if let Some(conv) = self.derive_goto(nid, target) {
+					vec![conv]
+				} else {
+					vec![]
+				}
+			}
+		}
+	}
+
+	fn returned_value<'a>(&mut self, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) -> Option<Vec<Name>> {
+		if self.return_to.is_empty() {
+			self.current_memory.check_dangling_return(nid, typing, sir);
+			Some(self.current_memory.leaves(&local_place(Local::from(0 as usize)), nid, typing, sir))
+		} else {
+			None
+		}
+	}
+
+	fn derive_return<'a>(&mut self, nid: NodeId, mirs: &MirMap<'a,'tcx>, tcx: TyCtxt<'a,'tcx,'tcx>, sir: &mut Sir) -> Vec<Frame<'tcx>> {
+		let mut return_to = self.return_to.clone();
+		if let Some(site) = return_to.pop() {
+			let ret_val = self.current_memory.return_value(nid, self.typing(mirs, tcx), sir);
+			let mut caller_memory = site.memory;
+			self.current_memory.remove_all_vars();
+			caller_memory.take_heap(&self.current_memory);
+			caller_memory.write(&site.destination, ret_val, nid, Typing::new(site.location.get_mir(mirs), tcx), sir);
+			if !self.should_examine(&site.location) {
+				return vec![];
+			}
+
+			let mut frame = self.from_new_loc(nid, site.location, None, caller_memory);
+			frame.return_to = return_to;
+			vec![frame]
+		} else {
+			vec![]
+		}
+	}
+
+	fn derive_switch_int<'a>(&self, generator: NodeId, discr: &Operand<'tcx>, switch_ty: Ty, values: &Cow<'tcx,[u128]>, mut targets: Vec<BasicBlock>, typing: Typing<'a,'tcx>, sir: &mut Sir) -> Vec<Frame<'tcx>> {
 		let mut new_mem = self.current_memory.clone();
-		let compare_to = new_mem.process_operand(discr.clone(), generator, sir);
+		let compare_to = new_mem.process_scalar(discr.clone(), generator, typing, sir);
 		let otherwise_target = targets.pop().unwrap();
 		let all_other = targets.iter(); 
 		let (mut expressions, mut frames): (Vec<Expr>, Vec<Option<Frame>>) = values.iter().zip(all_other).map(|(desired_val, target)| {
@@ -225,7 +421,7 @@ impl <'tcx> Frame<'tcx> {
 
 		let init_val = expressions.pop().unwrap();
 
-		let otherwise_expr = Expr::UnOp(Rator::Not, Box::new(expressions.into_iter().fold(init_val, |x,y| Expr::BinOp(Rator::And, Box::new(x), Box::new(y)))));
+		let otherwise_expr = Expr::UnOp(Rator::Not, Box::new(expressions.into_iter().fold(init_val, |x,y| Expr::BinOp(Rator::Or, Box::new(x), Box::new(y)))));
 
 		frames.push(self.block_to_frame(generator, otherwise_target, Some(otherwise_expr),new_mem));
 
@@ -251,71 +447,173 @@ impl <'tcx> Frame<'tcx> {
 	}
 
 
-	fn should_examine(&self, target_loc: &Location) -> bool {
+	fn should_examine(&self, target_loc: &Location<'tcx>) -> bool {
 		*self.seen_counts.get(target_loc).unwrap_or(&0) < MAX_UNROLL
 	}
 
 
 	fn remove_var(&mut self, lcl: Local) {
-		self.current_memory.remove_var(&Place::Base(PlaceBase::Local(lcl)));
+		self.current_memory.remove_var(lcl);
 	}
 
-	fn get_block_data(&self, mirs: &HashMap<DefId,&'tcx Mir<'tcx>>) -> &BasicBlockData<'tcx> {
+	fn get_block_data<'a>(&self, mirs: &MirMap<'a,'tcx>) -> &'a BasicBlockData<'tcx> {
 		self.current_loc.get_block_data(mirs)
 	}
 
-	fn get_statements(&self,mirs: &HashMap<DefId,&'tcx Mir<'tcx>>) -> &Vec<Statement<'tcx>> {
+	fn get_statements<'a>(&self,mirs: &MirMap<'a,'tcx>) -> &'a Vec<Statement<'tcx>> {
 		self.current_loc.get_statements(mirs)
 	}
 
+	fn add_var<'a>(&mut self, lcl: Local, typing: Typing<'a,'tcx>, sir: &mut Sir) {
+		self.current_memory.add_new_var(lcl, typing, sir, Some((self.current_loc.get_def_id(),lcl,vec![])));
+	}
+
+	fn set_discriminant<'a>(&mut self, plc: &Place<'tcx>, variant: VariantIdx, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) {
+		self.current_memory.set_discriminant(plc, variant, nid, typing, sir);
+	}
+
+	fn assign<'a>(&mut self, to: &Place<'tcx>, from: &Box<Rvalue<'tcx>>, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) { 
+		if let Rvalue::Aggregate(kind, rands) = &**from {
+			if let AggregateKind::Adt(def, _, substs, _, Some(active)) = **kind {
+				if def.is_union() {
+					self.assign_union(to, typing.variant_tys(def.non_enum_variant(), substs)[active], active, rands[0].clone(), nid, typing, sir);
+					return;
+				}
+			}
+		}
 
-	fn get_local_decl(&self, lcl: Local, mir: &HashMap<DefId,&'tcx Mir<'tcx>>) -> &LocalDecl<'tcx> {
-		self.current_loc.get_local_decl(lcl, mir)
+		let val = self.evaluate_rvalue(from.clone(),nid,typing,sir);
+		self.current_memory.write(to,val,nid,typing,sir);
 	}
 
-	fn add_var(&mut self, lcl: Local, mir: &HashMap<DefId,&'tcx Mir<'tcx>>, sir: &mut Sir) {
-		let plc = PlaceBase::Local(lcl);
-		let dcl = self.get_local_decl(lcl, mir);
-		self.current_memory.add_new_var(Place::Base(plc), dcl.ty,sir, Some((self.current_loc.get_def_id(),lcl)));
+	/*
+	 * Writes rand to the active field of the union at to. A union built from a zero sized field, as
+	 * MaybeUninit::uninit is, holds nothing else and is uninitialized.
+	 */
+	fn assign_union<'a>(&mut self, to: &Place<'tcx>, field_ty: Ty<'tcx>, active: usize, rand: Operand<'tcx>, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) {
+		let val = self.current_memory.process_operand(rand, nid, typing, sir);
+		if typing.size_of(field_ty) == Some(0) {
+			self.current_memory.write(to, Value::Uninit, nid, typing, sir);
+		} else {
+			self.current_memory.write(&to.clone().field(Field::new(active), field_ty), val, nid, typing, sir);
+		}
 	}
 
-	fn assign(&mut self, to: &Place<'tcx>, from: &Box<Rvalue<'tcx>>, nid: NodeId, sir: &mut Sir) { 
-		let expr = self.evaluate_rvalue(from.clone(),nid,sir);
-		let new_name = self.current_memory.new_assignment(to.clone(),nid,sir);
-		let set = Expr::BinOp(Rator::Eq, Box::new(Expr::Ref(new_name)), Box::new(expr));
-		sir.add_expr_to_node(nid,set);
+	/*
+	 * Casts rand to ty. Integers are truncated, or extended according to the signedness of their
+	 * source, and fieldless enums cast to their discriminant. Casting an integer to a pointer gives
+	 * a pointer into an allocation that is not known, and a function item becomes a non-null
+	 * function pointer. Floats are not modelled, so an integer cast from one is unconstrained.
+	 */
+	fn evaluate_cast<'a>(&mut self, rand: Operand<'tcx>, ty: Ty<'tcx>, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) -> Value {
+		let from_ty = typing.operand_ty(&rand);
+		let val = self.current_memory.process_operand(rand,nid,typing,sir);
+		match (BitWidth::of(from_ty), BitWidth::of(ty), &from_ty.sty) {
+			(Some(_), Some(_), _) if from_ty.is_region_ptr() && ty.is_unsafe_ptr() => {
+				let val = self.current_memory.derive_raw(val, from_ty, nid, typing, sir);
+				cast_pointer(val, from_ty, ty, typing)
+			},
+			(Some(_), Some(_), _) if typing.is_pointer(from_ty) && typing.is_pointer(ty) => cast_pointer(val, from_ty, ty, typing),
+			(Some(from), Some(to), _) => match val.scalar() {
+				Some(expr) => Value::Scalar(from.resize(expr, to)),
+				None => fresh_value(ty, typing, sir)
+			},
+			(None, Some(to), _) if from_ty.is_bool() => match val.scalar() {
+				Some(expr) => Value::Scalar(Expr::Ite(Box::new(expr), Box::new(Expr::Value(SymTy::Integer(1, to))), Box::new(Expr::Value(SymTy::Integer(0, to))))),
+				None => fresh_value(ty, typing, sir)
+			},
+			(None, Some(to), &TyKind::Adt(def, _)) if def.is_enum() => match (val, BitWidth::of(typing.discr_ty(def))) {
+				(Value::Enum(discr, _), Some(from)) => Value::Scalar(from.resize(discr, to)),
+				_ => fresh_value(ty, typing, sir)
+			},
+			(None, Some(_), &TyKind::FnDef(..)) | (None, Some(_), &TyKind::Closure(..)) => Value::Scalar(self.current_memory.fresh_pointer(nid, sir)),
+			(None, Some(_), _) if from_ty.is_floating_point() => fresh_value(ty, typing, sir),
+			(_, None, _) if ty.is_floating_point() => Value::Opaque,
+			_ => val
+		}
 	}
 
-	fn evaluate_rvalue(&mut self,rval: Box<Rvalue<'tcx>>, nid: NodeId, sir: &mut Sir) -> Expr {
+	fn evaluate_rvalue<'a>(&mut self,rval: Box<Rvalue<'tcx>>, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) -> Value {
 		match *rval {
-			Rvalue::Use(rand) => self.current_memory.process_operand(rand,nid,sir),
-			Rvalue::BinaryOp(binop, rand1, rand2) => Expr::BinOp(Rator::from_mir_bin(&binop),
-				Box::new(self.current_memory.process_operand(rand1,nid,sir)),Box::new(self.current_memory.process_operand(rand2,nid,sir))),
-			Rvalue::UnaryOp(unop, rand) => Expr::UnOp(Rator::from_mir_un(&unop), Box::new(self.current_memory.process_operand(rand,nid,sir))),
-			Rvalue::Cast(_,rand,_) => self.current_memory.process_operand(rand,nid,sir),
-			Rvalue::Ref(_,_,plc) => Expr::Ref(self.current_memory.process_plc(&plc,nid, sir)),
-			Rvalue::CheckedBinaryOp(binop,rand1,rand2) => Expr::BinOp(Rator::from_mir_bin(&binop),
-				Box::new(self.current_memory.process_operand(rand1,nid,sir)),Box::new(self.current_memory.process_operand(rand2,nid,sir))),
-			_ => unimplemented!(),
+			Rvalue::Use(rand) => self.current_memory.process_operand(rand,nid,typing,sir),
+			Rvalue::BinaryOp(binop, rand1, rand2) => {
+				let (lhs_ty, rhs_ty) = (typing.operand_ty(&rand1), typing.operand_ty(&rand2));
+				if lhs_ty.is_floating_point() {
+					return fresh_value(binop.ty(typing.tcx(), lhs_ty, rhs_ty), typing, sir);
+				}
+				let lhs = self.current_memory.process_operand(rand1,nid,typing,sir);
+				let rhs = self.current_memory.process_scalar(rand2,nid,typing,sir);
+				let prov = lhs.provenance();
+				let lhs = lhs.scalar().unwrap_or_else(|| fresh_scalar(lhs_ty, typing, sir));
+				if binop != BinOp::Offset {
+					return Value::Scalar(binop_expr(binop, lhs, rhs, lhs_ty, rhs_ty, typing));
+				}
+				match offset_expr(lhs, rhs, lhs_ty, rhs_ty, typing) {
+					Some(addr) => Value::Pointer(addr, prov),
+					None => Value::Pointer(fresh_scalar(lhs_ty, typing, sir), Provenance::unknown())
+				}
+			},
+			Rvalue::CheckedBinaryOp(binop,rand1,rand2) => {
+				let (lhs_ty, rhs_ty) = (typing.operand_ty(&rand1), typing.operand_ty(&rand2));
+				let lhs = self.current_memory.process_scalar(rand1,nid,typing,sir);
+				let rhs = self.current_memory.process_scalar(rand2,nid,typing,sir);
+				let overflow = overflow_expr(binop, lhs.clone(), rhs.clone(), lhs_ty, rhs_ty);
+				Value::Aggregate(vec![Value::Scalar(binop_expr(binop, lhs, rhs, lhs_ty, rhs_ty, typing)), Value::Scalar(overflow)])
+			},
+			Rvalue::UnaryOp(_, ref rand) if typing.operand_ty(rand).is_floating_point() => Value::Opaque,
+			Rvalue::UnaryOp(unop, rand) => Value::Scalar(Expr::UnOp(Rator::from_mir_un(&unop, typing.operand_ty(&rand)), Box::new(self.current_memory.process_scalar(rand,nid,typing,sir)))),
+			Rvalue::Cast(_,rand,ty) => self.evaluate_cast(rand,ty,nid,typing,sir),
+			Rvalue::Ref(_,kind,plc) => self.current_memory.borrow(&plc,kind,nid,typing,sir),
+			Rvalue::NullaryOp(NullOp::Box, ty) => self.current_memory.allocate(ty,nid,typing,sir),
+			Rvalue::NullaryOp(NullOp::SizeOf, ty) => match typing.size_of(ty) {
+				Some(size) => Value::Scalar(Expr::Value(SymTy::pointer(size as u128))),
+				None => fresh_value(typing.tcx().types.usize, typing, sir)
+			},
+			Rvalue::Aggregate(kind, rands) => {
+				let fields: Vec<Value> = rands.into_iter().map(|rand| self.current_memory.process_operand(rand,nid,typing,sir)).collect();
+				match *kind {
+					AggregateKind::Tuple | AggregateKind::Closure(..) | AggregateKind::Array(_) => Value::Aggregate(fields),
+					AggregateKind::Adt(def, ..) if def.is_struct() => Value::Aggregate(fields),
+					AggregateKind::Adt(def, variant, ..) if def.is_enum() => {
+						let mut fields = Some(fields);
+						let variants = (0..def.variants.len()).map(|i| if i == variant.index() {
+							Value::Aggregate(fields.take().unwrap())
+						} else {
+							Value::Opaque
+						}).collect();
+						Value::Enum(Expr::Value(typing.discriminant(def, variant)), variants)
+					},
+					_ => Value::Opaque
+				}
+			},
+			Rvalue::Discriminant(plc) => self.current_memory.discriminant(&plc,nid,typing,sir),
+			Rvalue::Len(plc) => self.current_memory.len(&plc,nid,typing,sir),
+			Rvalue::Repeat(rand, count) => {
+				let val = self.current_memory.process_operand(rand,nid,typing,sir);
+				if count <= MAX_ARRAY_LEN {
+					Value::Aggregate(vec![val; count as usize])
+				} else {
+					Value::Opaque
+				}
+			},
+			rval => fresh_value(rval.ty(typing.get_mir(), typing.tcx()), typing, sir)
 		}
 	}
 
-	fn create_entry(def_id: DefId, mir: &Mir<'tcx>, sir: &mut Sir) -> Frame<'tcx> {
+	fn create_entry<'a>(instance: Instance<'tcx>, typing: Typing<'a,'tcx>, sir: &mut Sir) -> Frame<'tcx> {
 		let bid = BasicBlock::from(0 as usize);
-		let args = mir.args_iter();
 		let frm = Frame {
 			seen_counts: HashMap::new(),
 			generator: None,
 			precondition: None,
-			current_memory: Memory::from_args(args, def_id, mir, sir),
-			current_loc: Location::new(def_id,bid),
+			current_memory: Memory::from_args(instance.def_id(), typing, sir),
+			current_loc: Location::new(instance,bid),
 			return_to: Vec::new()
 		};
 
 		frm
 	}
 
-
 	fn add_edge_to(&self, my_id: NodeId, sir: &mut Sir) {
 		if let Some(gen) = self.generator {
 			sir.add_edge(gen,Edge::new(self.precondition.clone(),my_id ))
@@ -323,35 +621,153 @@ impl <'tcx> Frame<'tcx> {
 	}
 }
 
-pub struct ExecutionContext<'tcx> {
-	mirs: HashMap<DefId,&'tcx Mir<'tcx>>,
+fn binop_expr<'a,'tcx>(binop: BinOp, lhs: Expr, rhs: Expr, lhs_ty: Ty<'tcx>, rhs_ty: Ty<'tcx>, typing: Typing<'a,'tcx>) -> Expr {
+	let rhs = match (binop, BitWidth::of(lhs_ty), BitWidth::of(rhs_ty)) {
+		(BinOp::Shl, Some(lhs_width), Some(rhs_width)) | (BinOp::Shr, Some(lhs_width), Some(rhs_width)) => {
+			let amount = rhs_width.unsigned().resize(rhs, lhs_width);
+			let mask = Expr::Value(SymTy::Integer(lhs_width.get_bits() as u128 - 1, lhs_width));
+			Expr::BinOp(Rator::BitAnd, Box::new(amount), Box::new(mask))
+		},
+		_ => rhs
+	};
+
+	Expr::BinOp(Rator::from_mir_bin(&binop, lhs_ty), Box::new(lhs), Box::new(rhs))
+}
+
+/*
+ * The address count elements past ptr, a pointer of type ptr_ty, or None if the size of its
+ * pointee is not known.
+ */
+fn offset_expr<'a,'tcx>(ptr: Expr, count: Expr, ptr_ty: Ty<'tcx>, count_ty: Ty<'tcx>, typing: Typing<'a,'tcx>) -> Option<Expr> {
+	let count = BitWidth::of(count_ty)?.resize(count, BitWidth::pointer());
+	let size = Expr::Value(SymTy::pointer(typing.pointee_size(ptr_ty)? as u128));
+	Some(Expr::BinOp(Rator::Add, Box::new(ptr), Box::new(Expr::BinOp(Rator::Mul, Box::new(count), Box::new(size)))))
+}
+
+/*
+ * The overflow flag of a CheckedBinaryOp. Arithmetic is redone at double width, where it cannot
+ * wrap, and compared against the extension of the truncated result.
+ */
+fn overflow_expr<'tcx>(binop: BinOp, lhs: Expr, rhs: Expr, lhs_ty: Ty<'tcx>, rhs_ty: Ty<'tcx>) -> Expr {
+	match (binop, BitWidth::of(lhs_ty), BitWidth::of(rhs_ty)) {
+		(BinOp::Shl, _, _) | (BinOp::Shr, _, _) => shift_overflow(rhs, lhs_ty, rhs_ty),
+		(BinOp::Add, Some(width), _) | (BinOp::Sub, Some(width), _) | (BinOp::Mul, Some(width), _) => {
+			let wide = BitWidth::new(width.get_bits() * 2, width.is_signed());
+			let full = Expr::BinOp(Rator::from_mir_bin(&binop, lhs_ty), Box::new(width.resize(lhs, wide)), Box::new(width.resize(rhs, wide)));
+			let wrapped = width.resize(wide.resize(full.clone(), width), wide);
+			Expr::BinOp(Rator::NotEqual, Box::new(wrapped), Box::new(full))
+		},
+		_ => Expr::Value(SymTy::from_boolean(false))
+	}
+}
+
+/*
+ * Holds when a shift of a lhs_ty by rhs is by at least the width of lhs_ty.
+ */
+fn shift_overflow<'tcx>(rhs: Expr, lhs_ty: Ty<'tcx>, rhs_ty: Ty<'tcx>) -> Expr {
+	match (BitWidth::of(lhs_ty), BitWidth::of(rhs_ty)) {
+		(Some(lhs_width), Some(rhs_width)) => {
+			let bits = Expr::Value(SymTy::Integer(lhs_width.get_bits() as u128, rhs_width.unsigned()));
+			Expr::BinOp(Rator::GreaterEqual, Box::new(rhs), Box::new(bits))
+		},
+		_ => Expr::Value(SymTy::from_boolean(false))
+	}
+}
+
+fn division_by_zero<'tcx>(rhs: Expr, ty: Ty<'tcx>) -> Expr {
+	Expr::BinOp(Rator::Eq, Box::new(rhs), Box::new(Expr::Value(SymTy::from_scalar(0, ty))))
+}
+
+/*
+ * Holds when lhs / rhs overflows, which only the minimum of a signed ty divided by -1 does.
+ */
+fn division_overflow<'tcx>(lhs: Expr, rhs: Expr, ty: Ty<'tcx>) -> Option<Expr> {
+	match BitWidth::of(ty) {
+		Some(width) if width.is_signed() => {
+			let min = Expr::BinOp(Rator::Eq, Box::new(lhs), Box::new(Expr::Value(SymTy::from_scalar(1 << (width.get_bits() - 1), ty))));
+			let minus_one = Expr::BinOp(Rator::Eq, Box::new(rhs), Box::new(Expr::Value(SymTy::from_scalar(u128::max_value(), ty))));
+			Some(Expr::BinOp(Rator::And, Box::new(min), Box::new(minus_one)))
+		},
+		_ => None
+	}
+}
+
+/*
+ * Whether a call to did at span that never returns is one the code promises never happens: an
+ * unreachable hint, or the panic of a failed debug_assert!.
+ */
+fn promised_unreachable<'a,'tcx>(did: DefId, span: Span, tcx: TyCtxt<'a,'tcx,'tcx>) -> bool {
+	intrinsics::intrinsic_name(did, tcx).map_or(false, |name| name == "unreachable")
+		|| models::defining_path(did, tcx) == "core::hint::unreachable_unchecked"
+		|| span.macro_backtrace().iter().any(|trace| trace.macro_decl_name.starts_with("debug_assert"))
+}
+
+/*
+ * The kind of obligation an assertion the compiler inserts before division and shifts reports, or
+ * None for the assertions that are not checked.
+ */
+fn arithmetic_panic<'tcx>(msg: &AssertMessage<'tcx>) -> Option<ObligationKind> {
+	match msg {
+		InterpError::DivisionByZero | InterpError::RemainderByZero => Some(ObligationKind::DivisionByZero),
+		InterpError::Overflow(BinOp::Div) | InterpError::Overflow(BinOp::Rem) => Some(ObligationKind::DivisionOverflow),
+		InterpError::Overflow(BinOp::Shl) | InterpError::Overflow(BinOp::Shr) => Some(ObligationKind::ShiftOverflow),
+		_ => None
+	}
+}
+
+pub struct ExecutionContext<'a,'tcx: 'a> {
+	tcx: TyCtxt<'a,'tcx,'tcx>,
+	mirs: MirMap<'a,'tcx>,
+	summaries: &'a mut SummaryCache<'tcx>,
 	stack: Vec<Frame<'tcx>>,
+	params: Vec<Name>,
+	returns: Vec<(NodeId, Vec<Name>)>,
+	touches_heap: bool,
+	contract: Option<(Contract, Vec<(Value, Ty<'tcx>)>)>,
 	result: Sir
 }
 
-impl <'tcx> ExecutionContext<'tcx> {
-	pub fn evaluate(mut self) -> (Sir, NodeId) {
+impl <'a,'tcx> ExecutionContext<'a,'tcx> {
+	pub fn evaluate(mut self) -> (Sir, NodeId, Summary) {
 		let mut entry = None;
 		while let Some(mut curr_frame) =  self.stack.pop() {
 			let cid = self.process_frame(&mut curr_frame);
+			self.touches_heap |= curr_frame.current_memory.heap_accessed();
 			if entry.is_none() {
 				entry = Some(cid);
 			}
 		}
 
-		(self.result, entry.unwrap())
+		let summary = Summary::from_sir(&self.result, self.params, &self.returns, self.touches_heap);
+		(self.result, entry.unwrap(), summary)
 	}
 
-	pub fn create_from_entry(entry: DefId, mirs: HashMap<DefId,&'tcx Mir<'tcx>>) -> ExecutionContext {
+	/*
+	 * Executes entry assuming the clauses in assumed of its arguments, along with its contract.
+	 */
+	pub fn create_from_entry(entry: Instance<'tcx>, assumed: Vec<P<ast::Expr>>, mirs: MirMap<'a,'tcx>, summaries: &'a mut SummaryCache<'tcx>, tcx: TyCtxt<'a,'tcx,'tcx>) -> ExecutionContext<'a,'tcx> {
 		let mut stack = Vec::new();
 		let mut result = Sir::new();
-		let frm = Frame::create_entry(entry, mirs.get(&entry).unwrap(), &mut result);
+		let typing = Typing::new(mirs.get(&entry).unwrap(), tcx);
+		let frm = Frame::create_entry(entry, typing, &mut result);
+		let params = frm.current_memory.arg_leaves(typing);
+		let contract = Contract::assuming(entry.def_id(), assumed, tcx).map(|contract| {
+			let args = frm.current_memory.arg_values(typing);
+			result.add_assumption(contract.requires(args.clone(), typing, tcx));
+			(contract, args)
+		});
 
 		stack.push(frm);
 
 		ExecutionContext {
+			tcx,
 			mirs,
+			summaries,
 			stack,
+			params,
+			returns: Vec::new(),
+			touches_heap: false,
+			contract,
 			result
 		}
 	}
@@ -370,20 +786,43 @@ impl <'tcx> ExecutionContext<'tcx> {
 
 	fn push_next_frames(&mut self, blk: BasicBlockData<'tcx>, curr_frame: &mut Frame<'tcx>, nid: NodeId) {
 		let term = blk.terminator();
-		for fr in curr_frame.derive_next_frames(nid, term, &mut self.result) {
+		if let TerminatorKind::Return = term.kind {
+			let typing = curr_frame.typing(&self.mirs, self.tcx);
+			if let Some(ret) = curr_frame.returned_value(nid, typing, &mut self.result) {
+				self.returns.push((nid, ret));
+				if let Some((contract, args)) = &self.contract {
+					let result = (curr_frame.current_memory.return_value(nid, typing, &mut self.result), typing.local_ty(Local::from(0 as usize)));
+					let ensures = contract.ensures(args.clone(), result, typing, self.tcx);
+					report(Expr::UnOp(Rator::Not, Box::new(ensures)), ObligationKind::PostconditionViolation, nid, &mut self.result);
+				}
+			}
+		}
+
+		for fr in curr_frame.derive_next_frames(nid, term, &self.mirs, self.tcx, self.summaries, &mut self.result) {
 			self.stack.push(fr);
 		}
 	}
 
+	/*
+	 * Inline assembly may write its outputs and any memory. Statements that only guide the borrow
+	 * checker do nothing.
+	 */
 	fn perform_statements(&mut self, curr_frame: &mut Frame<'tcx>, statements: &Vec<Statement<'tcx>>) -> NodeId {
 		let nid = self.result.add_node();
+		let typing = curr_frame.typing(&self.mirs, self.tcx);
 		for stat in statements {
 			match &stat.kind {
-				StatementKind::Assign(to,from) => curr_frame.assign(to,from, nid, &mut self.result),
-				StatementKind::StorageLive(lcl) => {curr_frame.add_var(*lcl,&self.mirs, &mut self.result)},
+				StatementKind::Assign(to,from) => curr_frame.assign(to,from, nid, typing, &mut self.result),
+				StatementKind::SetDiscriminant{place, variant_index} => curr_frame.set_discriminant(place, *variant_index, nid, typing, &mut self.result),
+				StatementKind::StorageLive(lcl) => {curr_frame.add_var(*lcl, typing, &mut self.result)},
 				StatementKind::StorageDead(lcl) => {curr_frame.remove_var(*lcl)},
-				StatementKind::Nop => (),
-				_ => unimplemented!(),
+				StatementKind::InlineAsm(asm) => {
+					curr_frame.current_memory.havoc_heap();
+					for out in asm.outputs.iter() {
+						curr_frame.current_memory.havoc(out, nid, typing, &mut self.result);
+					}
+				},
+				StatementKind::FakeRead(..) | StatementKind::Retag(..) | StatementKind::AscribeUserType(..) | StatementKind::Nop => ()
 			}
 		}
 		nid
@@ -0,0 +1,236 @@
+use rustc::hir::def_id::DefId;
+use rustc::hir::map::DefPathData;
+use rustc::ty::{Ty,TyCtxt,TyKind,DefIdTree};
+use crate::exec::driver::analysis_passes::sir::{Sir,NodeId,Expr,Rator,SymTy,ObligationKind};
+use super::memory::{Memory,Typing,Value,invalid_range,report,name_of,MAX_ARRAY_LEN};
+
+
+/*
+ * The path of did in the crate that defines it, whichever crate re-exports it to the code being
+ * analyzed, so that std::ptr::read is core::ptr::read. An impl is named after its type, as in
+ * alloc::vec::<impl Vec>::set_len.
+ */
+pub fn defining_path<'a,'tcx>(did: DefId, tcx: TyCtxt<'a,'tcx,'tcx>) -> String {
+	let mut ancestors = vec![did];
+	while let Some(parent) = tcx.parent(*ancestors.last().unwrap()) {
+		ancestors.push(parent);
+	}
+	ancestors.pop();
+
+	let mut path = tcx.crate_name(did.krate).to_string();
+	for ancestor in ancestors.into_iter().rev() {
+		path.push_str("::");
+		match tcx.def_key(ancestor).disambiguated_data.data {
+			DefPathData::Impl => {
+				let self_ty = tcx.type_of(ancestor);
+				let name = match self_ty.sty {
+					TyKind::Adt(def, _) => tcx.item_name(def.did).to_string(),
+					_ => self_ty.to_string()
+				};
+				path.push_str(&format!("<impl {}>", name));
+			},
+			data => path.push_str(&data.as_interned_str().as_str())
+		}
+	}
+	path
+}
+
+/*
+ * The path of a standard library function whose effect on memory is modelled here rather than
+ * read from its MIR.
+ */
+pub fn model_path<'a,'tcx>(did: DefId, tcx: TyCtxt<'a,'tcx,'tcx>) -> Option<String> {
+	let path = defining_path(did, tcx);
+	match path.as_str() {
+		"core::slice::from_raw_parts" | "core::slice::from_raw_parts_mut" => Some(path),
+		"core::slice::<impl [T]>::len" | "core::str::<impl str>::len" => Some(path),
+		"core::ptr::drop_in_place" | "core::ptr::real_drop_in_place" => Some(path),
+		"alloc::alloc::dealloc" | "alloc::alloc::__rust_dealloc" => Some(path),
+		"core::mem::transmute_copy" => Some(path),
+		"core::ptr::read" | "core::ptr::write" => Some(path),
+		"core::str::from_utf8_unchecked" => Some(path),
+		"alloc::vec::<impl Vec>::set_len" | "alloc::boxed::<impl Box>::from_raw" => Some(path),
+		_ => None
+	}
+}
+
+/*
+ * The result of calling the function at path on args. Unsafe functions also check the
+ * preconditions their documentation places on their arguments. A slice made from raw parts may
+ * only be accessed within the length it was given. The length of a slice is read out of a union
+ * in its MIR, so it is taken from the pointer directly. Deallocating takes the size from the
+ * layout, the first field of Layout. Transmuting by copy reads a dest_ty out of the memory of the
+ * source, which has to be large enough to hold it. The fields of a Vec are private, so set_len
+ * forgets the heap when they are not the ones it expects.
+ */
+pub fn evaluate<'a,'tcx>(path: &str, args: Vec<(Value, Ty<'tcx>)>, dest_ty: Ty<'tcx>, memory: &mut Memory<'tcx>, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) -> Option<Value> {
+	let mut args = args.into_iter();
+	match path {
+		"core::slice::from_raw_parts" | "core::slice::from_raw_parts_mut" => {
+			let (data, data_ty) = args.next()?;
+			let (len, _) = args.next()?;
+			let elem_size = typing.pointee_size(data_ty)?;
+			let size = Expr::BinOp(Rator::Mul, Box::new(len.clone().scalar()?), Box::new(Expr::Value(SymTy::pointer(elem_size as u128))));
+			let mut violation = invalid_range(&data, size.clone(), typing.pointee_align(data_ty)?)?;
+			if elem_size > 0 {
+				let too_long = Expr::BinOp(Rator::GreaterThan, Box::new(len.clone().scalar()?), Box::new(Expr::Value(SymTy::pointer((std::isize::MAX as u64 / elem_size) as u128))));
+				violation = Expr::BinOp(Rator::Or, Box::new(violation), Box::new(too_long));
+			}
+			report(violation, ObligationKind::PreconditionViolation, nid, sir);
+			let prov = data.provenance();
+			let addr = data.scalar()?;
+			Some(Value::Slice(addr.clone(), len.scalar()?, prov.within_range(addr, size)))
+		},
+		"core::slice::<impl [T]>::len" | "core::str::<impl str>::len" => {
+			let (slice, _) = args.next()?;
+			slice.length().map(Value::Scalar)
+		},
+		"core::ptr::drop_in_place" | "core::ptr::real_drop_in_place" => {
+			let (ptr, ptr_ty) = args.next()?;
+			memory.drop_at(ptr, ptr_ty.builtin_deref(true)?.ty, nid, typing, sir);
+			Some(Value::Aggregate(vec![]))
+		},
+		"alloc::alloc::dealloc" | "alloc::alloc::__rust_dealloc" => {
+			let (ptr, _) = args.next()?;
+			let size = match args.next()? {
+				(Value::Aggregate(mut layout), _) => layout.remove(0).scalar()?,
+				(val, _) => val.scalar()?
+			};
+			memory.free(ptr, size, Expr::Value(SymTy::from_boolean(true)), nid, sir);
+			Some(Value::Aggregate(vec![]))
+		},
+		"core::mem::transmute_copy" => {
+			let (src, _) = args.next()?;
+			Some(memory.load_at(src, dest_ty, nid, typing, sir))
+		},
+		"core::ptr::read" => {
+			let (src, src_ty) = args.next()?;
+			let ty = src_ty.builtin_deref(true)?.ty;
+			memory.check_alignment(&src.clone().scalar()?, ty, nid, typing, sir);
+			Some(memory.load_at(src, ty, nid, typing, sir))
+		},
+		"core::ptr::write" => {
+			let (dst, dst_ty) = args.next()?;
+			let (val, _) = args.next()?;
+			let ty = dst_ty.builtin_deref(true)?.ty;
+			memory.check_alignment(&dst.clone().scalar()?, ty, nid, typing, sir);
+			memory.store_at(dst, ty, val, nid, typing, sir);
+			Some(Value::Aggregate(vec![]))
+		},
+		"core::str::from_utf8_unchecked" => {
+			let (bytes, bytes_ty) = args.next()?;
+			let byte_ty = typing.slice_elem(bytes_ty.builtin_deref(true)?.ty)?;
+			if let Some(violation) = invalid_utf8(&bytes, byte_ty, memory, nid, typing, sir) {
+				report(violation, ObligationKind::PreconditionViolation, nid, sir);
+			}
+			Some(bytes)
+		},
+		"alloc::vec::<impl Vec>::set_len" => {
+			let (vec_ptr, vec_ptr_ty) = args.next()?;
+			let (new_len, _) = args.next()?;
+			let vec_ty = vec_ptr_ty.builtin_deref(true)?.ty;
+			let loaded = match memory.load_at(vec_ptr.clone(), vec_ty, nid, typing, sir) {
+				Value::Aggregate(fields) => Some(fields),
+				_ => None
+			};
+			let ((buf, len, cap, elem_ty), mut fields) = match (vec_fields(vec_ty, typing), loaded) {
+				(Some(layout), Some(fields)) => (layout, fields),
+				_ => {
+					typing.tcx().sess.warn("rure does not recognize the fields of this Vec, so set_len is not checked");
+					memory.havoc_heap();
+					return Some(Value::Aggregate(vec![]));
+				}
+			};
+			if let (Value::Aggregate(buf_fields), true) = (&fields[buf], typing.size_of(elem_ty).map_or(false, |size| size > 0)) {
+				let violation = Expr::BinOp(Rator::GreaterThan, Box::new(new_len.clone().scalar()?), Box::new(buf_fields[cap].clone().scalar()?));
+				report(violation, ObligationKind::PreconditionViolation, nid, sir);
+			}
+			fields[len] = new_len;
+			memory.store_at(vec_ptr, vec_ty, Value::Aggregate(fields), nid, typing, sir);
+			Some(Value::Aggregate(vec![]))
+		},
+		"alloc::boxed::<impl Box>::from_raw" => {
+			let (raw, raw_ty) = args.next()?;
+			let ty = raw_ty.builtin_deref(true)?.ty;
+			let mut violation = invalid_range(&raw, Expr::Value(SymTy::pointer(0)), typing.align_of(ty)?)?;
+			if typing.size_of(ty)? > 0 {
+				violation = Expr::BinOp(Rator::Or, Box::new(violation), Box::new(memory.not_boxed(&raw)));
+			}
+			report(violation, ObligationKind::PreconditionViolation, nid, sir);
+			Some(Value::Pointer(raw.clone().scalar()?, raw.provenance()))
+		},
+		_ => None
+	}
+}
+
+/*
+ * The buf, len and buf.cap fields of the Vec type vec_ty and its element type, or None if the Vec
+ * does not have them.
+ */
+fn vec_fields<'a,'tcx>(vec_ty: Ty<'tcx>, typing: Typing<'a,'tcx>) -> Option<(usize, usize, usize, Ty<'tcx>)> {
+	let (buf, len) = (typing.field_named(vec_ty, "buf")?, typing.field_named(vec_ty, "len")?);
+	let cap = typing.field_named(typing.field_tys(vec_ty)?[buf], "cap")?;
+	match vec_ty.sty {
+		TyKind::Adt(_, substs) => Some((buf, len, cap, substs.type_at(0))),
+		_ => None
+	}
+}
+
+/*
+ * The states of a UTF-8 decoder between bytes, and the transitions between them as the state, the
+ * range of bytes and the state they lead to. A byte no transition covers rejects the input.
+ * Overlong encodings, surrogates and code points past U+10FFFF are ruled out by the narrower
+ * ranges after E0, ED, F0 and F4.
+ */
+const UTF8_ACCEPT: u128 = 0;
+const UTF8_REJECT: u128 = 8;
+const UTF8_TRANSITIONS: [(u128, u128, u128, u128); 16] = [
+	(0, 0x00, 0x7f, 0),
+	(0, 0xc2, 0xdf, 1),
+	(0, 0xe0, 0xe0, 4),
+	(0, 0xe1, 0xec, 2),
+	(0, 0xed, 0xed, 5),
+	(0, 0xee, 0xef, 2),
+	(0, 0xf0, 0xf0, 6),
+	(0, 0xf1, 0xf3, 3),
+	(0, 0xf4, 0xf4, 7),
+	(1, 0x80, 0xbf, 0),
+	(2, 0x80, 0xbf, 1),
+	(3, 0x80, 0xbf, 2),
+	(4, 0xa0, 0xbf, 1),
+	(5, 0x80, 0x9f, 1),
+	(6, 0x90, 0xbf, 2),
+	(7, 0x80, 0x8f, 2)
+];
+
+/*
+ * The condition under which the byte slice bytes is not valid UTF-8, decoding it byte by byte, or
+ * None if its bytes are not known. Only slices of a known length, up to MAX_ARRAY_LEN, are checked,
+ * as the bytes of any other slice may well have been validated before.
+ */
+fn invalid_utf8<'a,'tcx>(bytes: &Value, byte_ty: Ty<'tcx>, memory: &mut Memory<'tcx>, nid: NodeId, typing: Typing<'a,'tcx>, sir: &mut Sir) -> Option<Expr> {
+	let len = match bytes.length()? {
+		Expr::Value(SymTy::Integer(len, _)) if len <= MAX_ARRAY_LEN as u128 => len,
+		_ => return None
+	};
+
+	let lit = |val: u128| Expr::Value(SymTy::from_scalar(val, byte_ty));
+	let (addr, prov) = (bytes.clone().scalar()?, bytes.provenance());
+	let mut state = lit(UTF8_ACCEPT);
+	for idx in 0..len {
+		let at = Expr::BinOp(Rator::Add, Box::new(addr.clone()), Box::new(Expr::Value(SymTy::pointer(idx))));
+		let byte = match memory.load_at(Value::Pointer(at, prov.clone()), byte_ty, nid, typing, sir) {
+			Value::Scalar(byte) => byte,
+			_ => return None
+		};
+		let next = UTF8_TRANSITIONS.iter().rev().fold(lit(UTF8_REJECT), |otherwise, &(from, lo, hi, to)| {
+			let within = Expr::BinOp(Rator::And,
+				Box::new(Expr::BinOp(Rator::GreaterEqual, Box::new(byte.clone()), Box::new(lit(lo)))),
+				Box::new(Expr::BinOp(Rator::LessEqual, Box::new(byte.clone()), Box::new(lit(hi)))));
+			let from = Expr::BinOp(Rator::Eq, Box::new(state.clone()), Box::new(lit(from)));
+			Expr::Ite(Box::new(Expr::BinOp(Rator::And, Box::new(from), Box::new(within))), Box::new(lit(to)), Box::new(otherwise))
+		});
+		state = Expr::Ref(name_of(next, typing.sort_of(byte_ty)?, nid, sir));
+	}
+	Some(Expr::BinOp(Rator::NotEqual, Box::new(state), Box::new(lit(UTF8_ACCEPT))))
+}
@@ -1,6 +1,7 @@
 
 mod analysis_passes;
 use analysis_passes::AnalysisHandler;
+use super::ExecutionConfig;
 
 
 use rustc::hir::Block;
@@ -13,28 +14,45 @@ use rustc_interface::interface::Compiler;
 use rustc::hir::map::Map;
 use rustc::hir::itemlikevisit::{ItemLikeVisitor};
 use rustc::hir::intravisit::{Visitor,FnKind,NestedVisitorMap};
-use rustc::hir::{BlockCheckMode, ImplItemKind,Item, TraitItem, ImplItem,ItemKind, HirId};
+use rustc::hir::{BlockCheckMode, ImplItemKind,Item, TraitItem, TraitItemKind, TraitMethod, ImplItem,ItemKind, HirId};
 use std::collections::HashSet;
 use rustc::hir::intravisit;
 
 
-struct GetTcntx {
-
+struct GetTcntx<'c> {
+    config: &'c ExecutionConfig
 }
 
-impl Callbacks for GetTcntx {
+impl <'c> Callbacks for GetTcntx<'c> {
     fn after_analysis(&mut self, compiler: &Compiler) -> bool {
         compiler.session().abort_if_errors();
         compiler.global_ctxt().unwrap().peek_mut().enter(|tcx| {
-            let ids = collect_target_func_ids(tcx.hir());
+            let ids = collect_target_func_ids(tcx.hir(), self.config.analyze_unsafe);
+            let mut summaries = analysis_passes::SummaryCache::new();
+            let cache = analysis_passes::ResultCache::new(self.config.cache_dir.clone());
+            let assumptions = match &self.config.assume_file {
+                Some(path) => analysis_passes::Assumptions::load(path, tcx.sess),
+                None => analysis_passes::Assumptions::new()
+            };
+            compiler.session().abort_if_errors();
 
             for id in ids {
-                let pass_handler = analysis_passes::AnalysisHandler::new(id, &tcx);
-                let errors = pass_handler.run_all_analyses();
-                for error in errors {
-                    let mut err = compiler.session().struct_span_err(error.get_span(),error.get_type());
-                    err.span_help(error.get_span(), &error.get_witness());
-                    err.emit();                
+                // A provided method that no concrete local impl inherits is verified in its generic
+                // form, where the layout of Self is not known and its memory is opaque.
+                let impls = analysis_passes::inheriting_impls(id, tcx);
+                let handlers = if impls.is_empty() {
+                    vec![analysis_passes::AnalysisHandler::new(id, &tcx)]
+                } else {
+                    impls.into_iter().map(|impl_did| analysis_passes::AnalysisHandler::inherited(id, impl_did, &tcx)).collect()
+                };
+
+                for pass_handler in handlers {
+                    let errors = pass_handler.run_all_analyses(&mut summaries, &cache, &assumptions);
+                    for error in errors {
+                        let mut err = compiler.session().struct_span_err(error.get_span(),error.get_type());
+                        err.span_help(error.get_span(), &error.get_witness());
+                        err.emit();                
+                    }
                 }
 
                 }
@@ -87,7 +105,8 @@ impl <'b,'y> ContainsUsafe<'b,'y> {
 
 struct IdCollector<'a,'hir: 'a> {
     ids: HashSet<HirId>,
-    comp_ctx: &'a Map<'hir>
+    comp_ctx: &'a Map<'hir>,
+    analyze_unsafe: bool
 }
 
 
@@ -100,13 +119,25 @@ impl <'a, 'hir: 'a> ItemLikeVisitor<'hir> for IdCollector<'a,'hir> {
                 if v.consume() {
                     self.ids.insert(item.hir_id);
                 }
+            } else if self.analyze_unsafe || analysis_passes::has_contract(&item.attrs) {
+                self.ids.insert(item.hir_id);
             }
         } 
 
     }
 
-    fn visit_trait_item(&mut self, _trait_item: &'hir TraitItem) {
-
+    fn visit_trait_item(&mut self, trait_item: &'hir TraitItem) {
+        if let TraitItemKind::Method(sig, TraitMethod::Provided(bid)) = &trait_item.node {
+            if sig.header.unsafety == Unsafety::Normal {
+                let mut v =  ContainsUsafe::new(&self.comp_ctx);
+                v.visit_fn(FnKind::Method(trait_item.ident, &sig, None, &trait_item.attrs),&sig.decl,*bid,trait_item.span,trait_item.hir_id);
+                if v.consume() {
+                    self.ids.insert(trait_item.hir_id);
+                }
+            } else if self.analyze_unsafe || analysis_passes::has_contract(&trait_item.attrs) {
+                self.ids.insert(trait_item.hir_id);
+            }
+        }
     }
 
     fn visit_impl_item(&mut self, impl_item: &'hir ImplItem) {
@@ -117,16 +148,19 @@ impl <'a, 'hir: 'a> ItemLikeVisitor<'hir> for IdCollector<'a,'hir> {
                 if v.consume() {
                     self.ids.insert(impl_item.hir_id);
                 }
+            } else if self.analyze_unsafe || analysis_passes::has_contract(&impl_item.attrs) {
+                self.ids.insert(impl_item.hir_id);
             }
         } 
     }
 }
 
 impl <'a,'hir> IdCollector<'a, 'hir> {
-    fn new(m: &'a Map<'hir>) -> IdCollector<'a,'hir> {
+    fn new(m: &'a Map<'hir>, analyze_unsafe: bool) -> IdCollector<'a,'hir> {
         IdCollector {
             ids: HashSet::new(),
-            comp_ctx: m
+            comp_ctx: m,
+            analyze_unsafe
         }
     }
 
@@ -136,8 +170,8 @@ impl <'a,'hir> IdCollector<'a, 'hir> {
     } 
 }
 
-fn collect_target_func_ids(code: &Map) -> Vec<DefId> {
-    let mut v =  IdCollector::new(code);
+fn collect_target_func_ids(code: &Map, analyze_unsafe: bool) -> Vec<DefId> {
+    let mut v =  IdCollector::new(code, analyze_unsafe);
     code.krate().visit_all_item_likes(&mut v);
     v.get_ids()
 }
@@ -163,12 +197,12 @@ fn find_sysroot() -> String {
     }
 }
 
-pub fn run_executor(mut rustc_args: Vec<String>) {
+pub fn run_executor(mut rustc_args: Vec<String>, config: &ExecutionConfig) {
     let sysroot_flag = String::from("--sysroot");
     if !rustc_args.contains(&sysroot_flag) {
         rustc_args.push(sysroot_flag);
         rustc_args.push(find_sysroot());
     }
 
-    rustc_driver::run_compiler(&rustc_args,&mut GetTcntx{},None,None).unwrap();
+    rustc_driver::run_compiler(&rustc_args,&mut GetTcntx{config},None,None).unwrap();
 }
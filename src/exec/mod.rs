@@ -1,17 +1,45 @@
 mod driver;
 
-pub struct ExecutionConfig {
+use std::path::PathBuf;
+
+const CACHE_DIR_FLAG: &str = "--rure-cache-dir=";
+const ANALYZE_UNSAFE_FLAG: &str = "--rure-analyze-unsafe";
+const ASSUME_FLAG: &str = "--rure-assume=";
 
+pub struct ExecutionConfig {
+    cache_dir: Option<PathBuf>,
+    analyze_unsafe: bool,
+    assume_file: Option<PathBuf>
 }
 
 impl ExecutionConfig {
     pub fn new() -> ExecutionConfig {
         ExecutionConfig {
-
+            cache_dir: None,
+            analyze_unsafe: false,
+            assume_file: None
         }
     }
 
+    pub fn from_args(args: &mut Vec<String>) -> ExecutionConfig {
+        let mut config = ExecutionConfig::new();
+        args.retain(|arg| if arg.starts_with(CACHE_DIR_FLAG) {
+            config.cache_dir = Some(PathBuf::from(&arg[CACHE_DIR_FLAG.len()..]));
+            false
+        } else if arg == ANALYZE_UNSAFE_FLAG {
+            config.analyze_unsafe = true;
+            false
+        } else if arg.starts_with(ASSUME_FLAG) {
+            config.assume_file = Some(PathBuf::from(&arg[ASSUME_FLAG.len()..]));
+            false
+        } else {
+            true
+        });
+
+        config
+    }
+
     pub fn run(&self,args: Vec<String>) {
-        let _exec = driver::run_executor(args);
+        let _exec = driver::run_executor(args, self);
     }
 }
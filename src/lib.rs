@@ -4,15 +4,19 @@ extern crate rustc_driver;
 extern crate rustc;
 extern crate rustc_interface;
 extern crate rustc_mir;
+extern crate rustc_data_structures;
+extern crate rustc_target;
+extern crate syntax;
 extern crate syntax_pos;
+extern crate serialize as rustc_serialize;
 #[macro_use] extern crate text_io;
 
 mod exec;
 
 use exec::ExecutionConfig;
 
-pub fn run(args: Vec<String>) {
-    let ExecutionConfig = ExecutionConfig::new();
+pub fn run(mut args: Vec<String>) {
+    let ExecutionConfig = ExecutionConfig::from_args(&mut args);
 
     ExecutionConfig.run(args);
 }
@@ -0,0 +1,10 @@
+#![crate_type="lib"]
+
+fn write_past_reborrow() -> u32 { //~ ERROR Aliasing Violation
+    let mut x = 0u32;
+    let p = &mut x as *mut u32;
+    let r = unsafe { &mut *p };
+    unsafe { *p = 1 };
+    *r = 2;
+    x
+}
@@ -0,0 +1,15 @@
+#![crate_type="lib"]
+
+fn aliased(p: &mut u32, q: *mut u32, r: *const u32) -> u32 { //~ ERROR Aliasing Violation
+//~^ ERROR Null Dereference
+    *p = 1;
+    unsafe {
+        if q == p as *mut u32 {
+            *q = 2;
+            if *p == 2 {
+                return *r;
+            }
+        }
+    }
+    0
+}
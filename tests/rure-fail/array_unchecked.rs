@@ -0,0 +1,7 @@
+#![crate_type="lib"]
+
+fn pick(i: usize) -> u32 { //~ ERROR Out of Bounds Access
+    let a = [1u32, 2, 3];
+    let s: &[u32] = &a;
+    unsafe { *s.get_unchecked(i) }
+}
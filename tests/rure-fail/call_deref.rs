@@ -0,0 +1,15 @@
+#![crate_type="lib"]
+
+unsafe fn load(p: *const u32) -> u32 {
+    *p
+}
+
+fn checked_load(p: *const u32, should_load: bool) -> u32 { //~ ERROR Null Dereference
+    if should_load {
+        unsafe {
+            load(p)
+        }
+    } else {
+        0
+    }
+}
@@ -0,0 +1,9 @@
+#![crate_type="lib"]
+#![feature(register_tool)]
+#![register_tool(rure)]
+
+#[rure::requires(i < n)] //~ WARN rure cannot check this contract clause
+unsafe fn pick(i: u8, n: u64) -> u32 { //~ ERROR Out of Bounds Access
+    let a = [1u32, 2, 3, 4];
+    *a.get_unchecked(i as usize)
+}
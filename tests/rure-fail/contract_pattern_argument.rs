@@ -0,0 +1,9 @@
+#![crate_type="lib"]
+#![feature(register_tool)]
+#![register_tool(rure)]
+
+#[rure::requires(i < 4)] //~ WARN `i` is not an argument bound by name
+unsafe fn pick((i, _): (usize, usize)) -> u32 { //~ ERROR Out of Bounds Access
+    let a = [1u32, 2, 3, 4];
+    *a.get_unchecked(i)
+}
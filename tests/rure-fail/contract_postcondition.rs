@@ -0,0 +1,12 @@
+#![crate_type="lib"]
+#![feature(register_tool)]
+#![register_tool(rure)]
+
+#[rure::requires(n < 100)]
+#[rure::ensures(result > n)]
+unsafe fn next(n: u32) -> u32 { //~ ERROR Postcondition Violation
+    if n == 7 {
+        return n;
+    }
+    n + 1
+}
@@ -0,0 +1,12 @@
+#![crate_type="lib"]
+#![feature(register_tool)]
+#![register_tool(rure)]
+
+#[rure::requires(!p.is_null())]
+unsafe fn load(p: *const u32) -> u32 {
+    *p
+}
+
+fn forward(p: *const u32) -> u32 { //~ ERROR Precondition Violation
+    unsafe { load(p) }
+}
@@ -0,0 +1,6 @@
+#![crate_type="lib"]
+
+fn dangling<'a>(x: u32) -> &'a u32 { //~ ERROR Dangling Reference
+    let y = x + 1;
+    unsafe { &*(&y as *const u32) }
+}
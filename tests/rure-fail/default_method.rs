@@ -0,0 +1,25 @@
+#![crate_type="lib"]
+
+trait Buffer {
+    fn data(&self) -> &[u8];
+
+    fn first(&self) -> u8 { //~ ERROR Out of Bounds Access
+        unsafe { *self.data().get_unchecked(0) }
+    }
+}
+
+struct Pair;
+
+impl Buffer for Pair {
+    fn data(&self) -> &[u8] {
+        &[1, 2]
+    }
+}
+
+struct Empty;
+
+impl Buffer for Empty {
+    fn data(&self) -> &[u8] {
+        &[]
+    }
+}
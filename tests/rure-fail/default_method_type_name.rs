@@ -0,0 +1,22 @@
+#![crate_type="lib"]
+#![allow(non_camel_case_types)]
+
+trait Buffer {
+    type first;
+
+    fn data(&self) -> &[u8];
+
+    fn first(&self) -> u8 { //~ ERROR Out of Bounds Access
+        unsafe { *self.data().get_unchecked(0) }
+    }
+}
+
+struct Empty;
+
+impl Buffer for Empty {
+    type first = u8;
+
+    fn data(&self) -> &[u8] {
+        &[]
+    }
+}
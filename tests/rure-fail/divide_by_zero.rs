@@ -0,0 +1,5 @@
+#![crate_type="lib"]
+
+fn ratio(total: u32, parts: u32) -> u32 { //~ ERROR Division by Zero
+    total / parts
+}
@@ -0,0 +1,14 @@
+#![crate_type="lib"]
+
+use std::alloc::{alloc, dealloc, Layout};
+
+fn free_twice(n: usize) { //~ ERROR Double Free
+    let layout = Layout::new::<u64>();
+    unsafe {
+        let p = alloc(layout);
+        dealloc(p, layout);
+        if n > 0 {
+            dealloc(p, layout);
+        }
+    }
+}
@@ -0,0 +1,5 @@
+#![crate_type="lib"]
+
+fn nth(s: &[u32], i: usize) -> u32 { //~ ERROR Out of Bounds Access
+    unsafe { *s.get_unchecked(i) }
+}
@@ -0,0 +1,6 @@
+#![feature(core_intrinsics)]
+#![crate_type="lib"]
+
+fn halve(x: u32) -> u32 { //~ ERROR Inexact Division
+    unsafe { std::intrinsics::exact_div(x, 2) }
+}
@@ -0,0 +1,7 @@
+#![crate_type="lib"]
+
+fn latin1() -> usize { //~ ERROR Precondition Violation
+    let bytes = [0x61u8, 0xe9];
+    let s = unsafe { std::str::from_utf8_unchecked(&bytes) };
+    s.len()
+}
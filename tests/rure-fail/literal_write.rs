@@ -0,0 +1,10 @@
+#![crate_type="lib"]
+
+fn shout() -> u8 { //~ ERROR Write to Read-Only Memory
+    let s = "quiet";
+    let p = s.as_ptr() as *mut u8;
+    unsafe {
+        *p = b'Q';
+        *p
+    }
+}
@@ -1,6 +1,6 @@
 #![crate_type="lib"]
 
-fn simple_max(a: usize, b: usize, c: *const usize) -> usize {
+fn simple_max(a: usize, b: usize, c: *const usize) -> usize { //~ ERROR Null Dereference
     if a + 1 > b {
         unsafe {
            *c
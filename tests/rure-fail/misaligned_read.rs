@@ -0,0 +1,9 @@
+#![crate_type="lib"]
+
+fn read_u32(buf: &[u8]) -> u32 { //~ ERROR Misaligned Dereference
+    if buf.len() >= 5 {
+        unsafe { *(buf.as_ptr().add(1) as *const u32) }
+    } else {
+        0
+    }
+}
@@ -0,0 +1,11 @@
+#![crate_type="lib"]
+
+fn skip(a: [u32; 2], n: isize) -> u32 { //~ ERROR Out of Bounds Offset
+    let p = &a as *const [u32; 2] as *const u32;
+    let q = unsafe { p.offset(n) };
+    if n == 0 {
+        unsafe { *q }
+    } else {
+        0
+    }
+}
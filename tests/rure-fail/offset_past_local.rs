@@ -0,0 +1,12 @@
+#![crate_type="lib"]
+
+fn read_past(x: u32, n: isize) -> u32 { //~ ERROR Out of Bounds Offset
+//~^ ERROR Out of Bounds Access
+    let p = &x as *const u32;
+    unsafe {
+        if n > 0 {
+            return *p.offset(n);
+        }
+    }
+    x
+}
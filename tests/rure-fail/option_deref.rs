@@ -0,0 +1,8 @@
+#![crate_type="lib"]
+
+fn load(p: Option<*const u32>) -> u32 { //~ ERROR Null Dereference
+    match p {
+        Some(ptr) => unsafe { *ptr },
+        None => 0
+    }
+}
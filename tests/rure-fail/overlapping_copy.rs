@@ -0,0 +1,6 @@
+#![crate_type="lib"]
+
+fn shift(buf: &mut [u32; 4]) { //~ ERROR Precondition Violation
+    let p = buf.as_mut_ptr();
+    unsafe { std::ptr::copy_nonoverlapping(p, p.add(1), 2) };
+}
@@ -0,0 +1,8 @@
+#![crate_type="lib"]
+
+fn past_len(p: &u32) -> u32 { //~ ERROR Out of Bounds Access
+    unsafe {
+        let s = std::slice::from_raw_parts(p as *const u32, 1);
+        *s.as_ptr().add(1)
+    }
+}
@@ -0,0 +1,6 @@
+#![crate_type="lib"]
+
+fn read_char(x: u32) -> char { //~ ERROR Invalid Value
+    let p = &x as *const u32 as *const char;
+    unsafe { *p }
+}
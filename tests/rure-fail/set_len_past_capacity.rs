@@ -0,0 +1,5 @@
+#![crate_type="lib"]
+
+fn expose(v: &mut Vec<u8>, n: usize) { //~ ERROR Precondition Violation
+    unsafe { v.set_len(n) };
+}
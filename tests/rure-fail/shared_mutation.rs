@@ -0,0 +1,8 @@
+#![crate_type="lib"]
+
+fn write_through_shared() -> u32 { //~ ERROR Write to Read-Only Memory
+    let x = 0u32;
+    let r = &x;
+    unsafe { *(r as *const u32 as *mut u32) = 1 };
+    x
+}
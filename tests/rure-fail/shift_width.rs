@@ -0,0 +1,5 @@
+#![crate_type="lib"]
+
+fn scale(x: u32, bits: u32) -> u32 { //~ ERROR Shift Overflow
+    x << bits
+}
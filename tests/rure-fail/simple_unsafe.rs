@@ -1,6 +1,6 @@
 #![crate_type="lib"]
 
-fn stupid_func(p: usize, should_deref: bool) -> u32 {
+fn stupid_func(p: usize, should_deref: bool) -> u32 { //~ ERROR Null Dereference
 
     let v: *const u32 = p as *const u32;
     if should_deref {
@@ -0,0 +1,10 @@
+#![crate_type="lib"]
+
+static LIMIT: u32 = 10;
+
+fn raise(by: u32) -> u32 { //~ ERROR Write to Read-Only Memory
+    unsafe {
+        *(&LIMIT as *const u32 as *mut u32) = by;
+    }
+    LIMIT
+}
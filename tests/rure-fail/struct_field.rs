@@ -0,0 +1,16 @@
+#![crate_type="lib"]
+
+struct Load {
+    ptr: *const u32,
+    enabled: bool
+}
+
+fn load(req: Load) -> u32 { //~ ERROR Null Dereference
+    if req.enabled {
+        unsafe {
+            *req.ptr
+        }
+    } else {
+        0
+    }
+}
@@ -0,0 +1,5 @@
+#![crate_type="lib"]
+
+fn as_bool(x: u8) -> bool { //~ ERROR Invalid Value
+    unsafe { std::mem::transmute::<u8, bool>(x) }
+}
@@ -0,0 +1,5 @@
+#![crate_type="lib"]
+
+fn widen(x: u16) -> u32 { //~ ERROR Out of Bounds Access
+    unsafe { std::mem::transmute_copy(&x) }
+}
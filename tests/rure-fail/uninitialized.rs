@@ -0,0 +1,6 @@
+#![crate_type="lib"]
+
+fn garbage() -> u32 { //~ ERROR Uninitialized Read
+    let x: u32 = unsafe { std::mem::uninitialized() };
+    x + 1
+}
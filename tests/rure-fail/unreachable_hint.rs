@@ -0,0 +1,8 @@
+#![crate_type="lib"]
+
+fn digit(c: u8) -> u8 { //~ ERROR Reachable Unreachable
+    match c {
+        b'0'..=b'9' => c - b'0',
+        _ => unsafe { std::hint::unreachable_unchecked() }
+    }
+}
@@ -0,0 +1,6 @@
+// compile-flags: --rure-analyze-unsafe
+#![crate_type="lib"]
+
+unsafe fn nth(s: &[u32], i: usize) -> u32 { //~ ERROR Out of Bounds Access
+    *s.get_unchecked(i)
+}
@@ -0,0 +1,8 @@
+#![crate_type="lib"]
+
+fn after_drop(x: u32) -> u32 { //~ ERROR Use After Free
+    let b = Box::new(x);
+    let p = &*b as *const u32;
+    drop(b);
+    unsafe { *p }
+}
@@ -0,0 +1,12 @@
+// compile-flags: -Z force-overflow-checks=off
+#![crate_type="lib"]
+
+fn deref_on_wrap(a: u8, p: *const u8) -> u8 { //~ ERROR Null Dereference
+    if a + 1 < a {
+        unsafe {
+            *p
+        }
+    } else {
+        0
+    }
+}
@@ -0,0 +1,13 @@
+#![crate_type="lib"]
+
+fn aligned_read(p: *const u64, align: usize) -> u64 {
+    let addr = p as usize;
+    let aligned = addr & !(align - 1);
+    if aligned != 0 && aligned >> 3 == addr >> 3 {
+        unsafe {
+            *(aligned as *const u64)
+        }
+    } else {
+        0
+    }
+}
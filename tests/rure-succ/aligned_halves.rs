@@ -0,0 +1,5 @@
+#![crate_type="lib"]
+
+fn high_half(x: &u64) -> u32 {
+    unsafe { *(x as *const u64 as *const u32).add(1) }
+}
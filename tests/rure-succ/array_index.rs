@@ -0,0 +1,10 @@
+#![crate_type="lib"]
+
+fn sum(i: usize, p: *const u32) -> u32 {
+    let a = [1u32, 2, 3];
+    let x = a[i % 3];
+    if x == 0 {
+        return unsafe { *p };
+    }
+    x + a[2]
+}
@@ -0,0 +1,6 @@
+// compile-flags: --rure-analyze-unsafe --rure-assume=tests/rure-succ/assumed_unsafe_fn.assume
+#![crate_type="lib"]
+
+unsafe fn nth(s: &[u32], i: usize) -> u32 {
+    *s.get_unchecked(i)
+}
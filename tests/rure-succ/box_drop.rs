@@ -0,0 +1,9 @@
+#![crate_type="lib"]
+
+fn read_then_drop(x: u32) -> u32 {
+    let b = Box::new(x);
+    let p = &*b as *const u32;
+    let y = unsafe { *p };
+    drop(b);
+    y
+}
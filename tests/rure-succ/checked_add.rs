@@ -0,0 +1,12 @@
+#![crate_type="lib"]
+
+fn deref_after_overflow(a: u8, p: *const u8) -> u8 {
+    let b = a + 1;
+    if b == 0 {
+        unsafe {
+            *p
+        }
+    } else {
+        b
+    }
+}
@@ -0,0 +1,15 @@
+#![crate_type="lib"]
+
+#[repr(u8)]
+enum Level {
+    Low = 0,
+    High = 1
+}
+
+fn level(x: u8) -> Level {
+    if x <= 1 {
+        unsafe { std::mem::transmute::<u8, Level>(x) }
+    } else {
+        Level::Low
+    }
+}
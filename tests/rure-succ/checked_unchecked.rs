@@ -0,0 +1,9 @@
+#![crate_type="lib"]
+
+fn nth(s: &[u32], i: usize) -> u32 {
+    if i < s.len() {
+        unsafe { *s.get_unchecked(i) }
+    } else {
+        0
+    }
+}
@@ -0,0 +1,7 @@
+#![crate_type="lib"]
+
+fn bumped(x: u8) -> u8 {
+    let bump = |a: u8| a.wrapping_add(1);
+    let p = &x as *const u8;
+    bump(unsafe { *p })
+}
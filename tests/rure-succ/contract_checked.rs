@@ -0,0 +1,20 @@
+#![crate_type="lib"]
+#![feature(register_tool)]
+#![register_tool(rure)]
+
+#[rure::requires(d != 0)]
+#[rure::ensures(result <= n)]
+unsafe fn quotient(n: u32, d: u32) -> u32 {
+    n / d
+}
+
+fn checked_quotient(n: u32, d: u32) -> u32 {
+    if d == 0 {
+        return 0;
+    }
+    let q = unsafe { quotient(n, d) };
+    if q > n {
+        return unsafe { *(0 as *const u32) };
+    }
+    q
+}
@@ -0,0 +1,29 @@
+#![crate_type="lib"]
+
+trait Buffer {
+    fn data(&self) -> &[u8];
+
+    fn first(&self) -> u8 {
+        unsafe { *self.data().get_unchecked(0) }
+    }
+}
+
+struct Pair;
+
+impl Buffer for Pair {
+    fn data(&self) -> &[u8] {
+        &[1, 2]
+    }
+}
+
+struct Single;
+
+impl Buffer for Single {
+    fn data(&self) -> &[u8] {
+        &[3]
+    }
+
+    fn first(&self) -> u8 {
+        3
+    }
+}
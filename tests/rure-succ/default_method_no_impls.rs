@@ -0,0 +1,7 @@
+#![crate_type="lib"]
+
+trait Header: Sized {
+    fn tag(&self) -> u8 {
+        unsafe { *(self as *const Self as *const u8) }
+    }
+}
@@ -0,0 +1,15 @@
+#![crate_type="lib"]
+
+trait Header: Sized {
+    fn tag(&self) -> u8 {
+        unsafe { *(self as *const Self as *const u8) }
+    }
+}
+
+struct Byte(u8);
+
+impl Header for Byte {
+    fn tag(&self) -> u8 {
+        self.0
+    }
+}
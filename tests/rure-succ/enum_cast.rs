@@ -0,0 +1,14 @@
+#![crate_type="lib"]
+
+enum Level {
+    Low = 1,
+    High = 4
+}
+
+fn weight(level: Level, p: *const u32) -> u32 {
+    let w = level as u32;
+    if w == 0 {
+        return unsafe { *p };
+    }
+    w
+}
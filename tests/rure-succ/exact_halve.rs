@@ -0,0 +1,10 @@
+#![feature(core_intrinsics)]
+#![crate_type="lib"]
+
+fn halve(x: u32) -> u32 {
+    if x % 2 == 0 {
+        unsafe { std::intrinsics::exact_div(x, 2) }
+    } else {
+        0
+    }
+}
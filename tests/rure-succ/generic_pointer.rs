@@ -0,0 +1,9 @@
+#![crate_type="lib"]
+
+fn read_or<T: Copy>(p: *const T, default: &T) -> T {
+    if p as usize == 0 {
+        *default
+    } else {
+        unsafe { *p }
+    }
+}
@@ -0,0 +1,9 @@
+#![crate_type="lib"]
+
+fn average(total: i32, count: i32) -> i32 {
+    if count <= 0 {
+        0
+    } else {
+        total / count
+    }
+}
@@ -0,0 +1,11 @@
+#![crate_type="lib"]
+
+fn digit(c: u8) -> u8 {
+    if c < b'0' || c > b'9' {
+        return 0;
+    }
+    match c {
+        b'0'..=b'9' => c - b'0',
+        _ => unsafe { std::hint::unreachable_unchecked() }
+    }
+}
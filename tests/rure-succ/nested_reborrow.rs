@@ -0,0 +1,13 @@
+#![crate_type="lib"]
+
+fn nested(p: *const u32) -> u32 {
+    let mut x = 0u32;
+    let r = &mut x;
+    let s = &mut *r;
+    *s = 1;
+    *r = 2;
+    if x != 2 {
+        return unsafe { *p };
+    }
+    x
+}
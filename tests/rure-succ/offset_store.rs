@@ -0,0 +1,12 @@
+#![crate_type="lib"]
+
+fn store_second(pair: &mut (u32, u32), r: *const u32) -> u32 {
+    let base = pair as *mut (u32, u32) as *mut u32;
+    unsafe {
+        *base.add(1) = 7;
+        if pair.1 != 7 {
+            return *r;
+        }
+    }
+    pair.0
+}
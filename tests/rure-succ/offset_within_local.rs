@@ -0,0 +1,6 @@
+#![crate_type="lib"]
+
+fn second(x: (u32, u32)) -> u32 {
+    let p = &x as *const (u32, u32) as *const u32;
+    unsafe { *p.offset(1) }
+}
@@ -0,0 +1,9 @@
+#![crate_type="lib"]
+
+fn load_checked(p: *const u32) -> u32 {
+    let opt = if p as usize == 0 { None } else { Some(p) };
+    match opt {
+        Some(ptr) => unsafe { *ptr },
+        None => 0
+    }
+}
@@ -0,0 +1,10 @@
+#![crate_type="lib"]
+
+fn middle(p: *const u32) -> u32 {
+    let a = [1u32, 2, 3];
+    let s = unsafe { std::slice::from_raw_parts(a.as_ptr(), 3) };
+    if s.len() != 3 {
+        return unsafe { *p };
+    }
+    s[1]
+}
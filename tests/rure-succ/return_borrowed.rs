@@ -0,0 +1,10 @@
+#![crate_type="lib"]
+
+fn pick<'a>(p: &'a u32, q: u32) -> &'a u32 {
+    let r = &q as *const u32;
+    if unsafe { *r } > 3 {
+        p
+    } else {
+        p
+    }
+}
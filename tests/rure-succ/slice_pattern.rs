@@ -0,0 +1,9 @@
+#![crate_type="lib"]
+#![feature(slice_patterns)]
+
+fn last(s: &[u32]) -> u32 {
+    match s {
+        [.., last] => *last,
+        [] => 0
+    }
+}
@@ -0,0 +1,13 @@
+#![crate_type="lib"]
+
+static mut COUNT: u32 = 0;
+
+fn bump(p: *const u32) -> u32 {
+    unsafe {
+        COUNT = 5;
+        if COUNT != 5 {
+            return *p;
+        }
+        COUNT
+    }
+}
@@ -0,0 +1,13 @@
+#![crate_type="lib"]
+
+fn store_then_load(p: *const u32) -> u32 {
+    let mut x = 0u32;
+    let q = &mut x as *mut u32;
+    unsafe {
+        *q = 5;
+        if x != 5 {
+            return *p;
+        }
+    }
+    x
+}
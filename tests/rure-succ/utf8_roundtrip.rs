@@ -0,0 +1,7 @@
+#![crate_type="lib"]
+
+fn roundtrip(s: &str) -> usize {
+    let bytes = s.as_bytes();
+    let t = unsafe { std::str::from_utf8_unchecked(bytes) };
+    t.len()
+}
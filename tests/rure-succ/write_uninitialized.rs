@@ -0,0 +1,7 @@
+#![crate_type="lib"]
+
+fn init_through_pointer() -> u32 {
+    let mut x: u32 = unsafe { std::mem::uninitialized() };
+    unsafe { std::ptr::write(&mut x, 7) };
+    x
+}